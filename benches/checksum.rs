@@ -0,0 +1,41 @@
+//! Checksum throughput, which is where the `simd` feature makes a difference. Compare runs with and without it:
+//! `RUSTFLAGS="-C target-cpu=native" cargo bench --features simd --bench checksum`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZES: [usize; 3] = [4 * 1024, 64 * 1024, 16 * 1024 * 1024];
+
+fn pseudo_random(len: usize) -> Vec<u8> {
+    (0..len as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+        .collect()
+}
+
+fn checksums(c: &mut Criterion) {
+    let data = pseudo_random(SIZES[SIZES.len() - 1]);
+
+    let mut group = c.benchmark_group("crc32");
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &data[..size],
+            |b, data| b.iter(|| zlib_rs::crc32(0, data)),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("adler32");
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &data[..size],
+            |b, data| b.iter(|| zlib_rs::adler32(1, data)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, checksums);
+criterion_main!(benches);
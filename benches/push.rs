@@ -0,0 +1,149 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use napi::bindgen_prelude::{Buffer, Either, Uint8Array};
+use vladfrangu_dev_compression::deflate::{DeflateCompressor, FlushMode};
+use vladfrangu_dev_compression::format::CompressionFormat;
+use vladfrangu_dev_compression::oneshot::{compress_deflate, decompress_all_frames};
+use vladfrangu_dev_compression::zlib::ZlibDecompressor;
+
+const PAYLOAD_SIZE: usize = 64 * 1024;
+const CHUNK_SIZE: u32 = 16 * 1024;
+
+/// Incompressible data, from a xorshift generator so every run sees the same bytes.
+fn random_data() -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+    (0..PAYLOAD_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+/// Highly compressible data.
+fn repeated_data() -> Vec<u8> {
+    b"compression-lib "
+        .iter()
+        .copied()
+        .cycle()
+        .take(PAYLOAD_SIZE)
+        .collect()
+}
+
+/// Back-to-back gateway dispatches, shaped like the `MESSAGE_CREATE` events Discord sends.
+fn json_data() -> Vec<u8> {
+    let mut payload = String::with_capacity(PAYLOAD_SIZE);
+    let mut sequence = 0u64;
+
+    while payload.len() < PAYLOAD_SIZE {
+        sequence += 1;
+        let id = 1_150_000_000_000_000_000 + sequence * 7_919;
+
+        payload.push_str(&format!(
+            concat!(
+                r#"{{"t":"MESSAGE_CREATE","s":{},"op":0,"d":{{"type":0,"tts":false,"timestamp":"2024-05-01T12:00:{:02}.000000+00:00","#,
+                r#""pinned":false,"mentions":[],"mention_roles":[],"mention_everyone":false,"id":"{}","flags":0,"embeds":[],"#,
+                r#""edited_timestamp":null,"content":"message number {} in the benchmark channel","components":[],"#,
+                r#""channel_id":"1149999999999999999","author":{{"username":"user{}","public_flags":0,"id":"{}","#,
+                r#""global_name":"User {}","discriminator":"0","avatar":null}},"attachments":[],"guild_id":"1100000000000000000"}}}}"#,
+            ),
+            sequence,
+            sequence % 60,
+            id,
+            sequence,
+            sequence % 97,
+            id / 3,
+            sequence % 97,
+        ));
+    }
+
+    payload.into_bytes()
+}
+
+fn into_bytes(data: Either<Buffer, napi::bindgen_prelude::Null>) -> Vec<u8> {
+    match data {
+        Either::A(buffer) => buffer.to_vec(),
+        Either::B(_) => Vec::new(),
+    }
+}
+
+/// Compresses `data` the way a gateway connection does, as a single message ending in a sync flush.
+fn sync_flushed(data: &[u8]) -> Vec<u8> {
+    let mut compressor = DeflateCompressor::new(Some(Either::A(CHUNK_SIZE)), None).unwrap();
+
+    let mut compressed = into_bytes(
+        compressor
+            .push(Uint8Array::new(data.to_vec()))
+            .unwrap()
+            .data,
+    );
+    compressed.extend(into_bytes(compressor.finish(FlushMode::Sync).unwrap().data));
+
+    compressed
+}
+
+fn inputs() -> [(&'static str, Vec<u8>); 3] {
+    [
+        ("random", random_data()),
+        ("repeated", repeated_data()),
+        ("json", json_data()),
+    ]
+}
+
+fn zlib_decompressor_push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ZlibDecompressor::push");
+
+    for (name, data) in inputs() {
+        let compressed = sync_flushed(&data);
+        group.throughput(Throughput::BytesDecimal(data.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &compressed,
+            |b, compressed| {
+                b.iter_batched(
+                    || {
+                        (
+                            ZlibDecompressor::new(Some(Either::A(CHUNK_SIZE)), None).unwrap(),
+                            Uint8Array::new(compressed.clone()),
+                        )
+                    },
+                    |(mut decompressor, input)| decompressor.push(input).unwrap(),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn decompress_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress_all_frames");
+
+    for (name, data) in inputs() {
+        let compressed = compress_deflate(Buffer::from(data.clone()), None)
+            .unwrap()
+            .to_vec();
+        group.throughput(Throughput::BytesDecimal(data.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &compressed,
+            |b, compressed| {
+                b.iter_batched(
+                    || Buffer::from(compressed.clone()),
+                    |input| decompress_all_frames(input, CompressionFormat::Zlib).unwrap(),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, zlib_decompressor_push, decompress_all);
+criterion_main!(benches);
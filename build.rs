@@ -1,5 +1,12 @@
 extern crate napi_build;
 
 fn main() {
+    // WebAssembly builds don't link against Node-API, see src/lib.rs
+    if std::env::var("CARGO_CFG_TARGET_FAMILY")
+        .is_ok_and(|family| family.split(',').any(|f| f == "wasm"))
+    {
+        return;
+    }
+
     napi_build::setup();
 }
@@ -0,0 +1,22 @@
+//! Feeds arbitrary input through `HttpDecompressor`, which inflates gzip without waiting for sync-flush suffixes.
+//! Malformed input must only ever produce `ok: false`, never a panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use napi::bindgen_prelude::Uint8Array;
+use vladfrangu_dev_compression::http::HttpDecompressor;
+
+fuzz_target!(|data: &[u8]| {
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+
+    // Split the input in two, so state carried over between pushes is exercised too
+    let (first, second) = data.split_at(data.len() / 2);
+    for chunk in [first, second] {
+        let result = decompressor.push(Uint8Array::new(chunk.to_vec())).unwrap();
+        if !result.ok {
+            return;
+        }
+    }
+
+    let _ = decompressor.finish().unwrap();
+});
@@ -0,0 +1,20 @@
+//! Feeds arbitrary input through `ZlibDecompressor`. Malformed input must only ever produce `ok: false`, never a
+//! panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use napi::bindgen_prelude::Uint8Array;
+use vladfrangu_dev_compression::zlib::ZlibDecompressor;
+
+const Z_SYNC_FLUSH_SUFFIX: &[u8] = &[0, 0, 255, 255];
+
+fuzz_target!(|data: &[u8]| {
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    // Messages are only inflated once the buffered input ends with a sync-flush suffix, so append one to make sure
+    // the input actually reaches inflate
+    let mut message = data.to_vec();
+    message.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+
+    let _ = decompressor.push(Uint8Array::new(message)).unwrap();
+});
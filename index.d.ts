@@ -3,7 +3,928 @@
 
 /* auto-generated by NAPI-RS */
 
-export declare class ZlibDecompressor {
+/**
+ * Hex-decodes `hex` and inflates the resulting zlib stream.
+ *
+ * Invalid hex input is reported with an `InvalidArg` status, while zlib failures use `GenericFailure`.
+ */
+export declare function decompressHexSync(hex: string): Buffer
+
+/**
+ * Compresses `data` into a zlib-wrapped deflate stream (with an Adler-32 trailer).
+ *
+ * `level` defaults to 6.
+ */
+export declare function compressDeflate(data: Buffer, level?: number | undefined | null): Buffer
+/**
+ * Compresses `data` on its own using Huffman coding only, skipping LZ77 match finding (`Z_HUFFMAN_ONLY`).
+ *
+ * This is cheaper than regular deflate for data that has already been de-duplicated. The result is raw deflate data,
+ * block headers included but without the zlib header and trailer, with the last block marked as final.
+ *
+ * Deflate ends a block whenever its buffer of 16K symbols fills up, which takes one symbol per byte here, so only
+ * inputs of up to 16383 bytes come out as a single block. Larger ones come out as several blocks, each with its own
+ * Huffman codes, of which only the last is marked as final, so the result is still one raw deflate stream.
+ */
+export declare function deflateHuffmanOnlyBlock(data: Buffer): Buffer
+/**
+ * Batch version of `deflateHuffmanOnlyBlock`, compressing every buffer on its own, in parallel.
+ *
+ * The result holds the raw deflate data of every buffer back to back, in order. Each one ends with a final block, so
+ * `decompressAllFrames(result, CompressionFormat.Deflate)` splits them apart again.
+ */
+export declare function huffmanOnlyCompressAll(data: Array<Buffer>): Buffer
+/**
+ * Compresses `data` into a zlib-wrapped deflate stream at increasing levels, starting from 1, until the output is
+ * at least `target_ratio` smaller than the input (`1 - compressed / input >= target_ratio`) or `max_time_ms`
+ * milliseconds have passed.
+ *
+ * Returns the output of the last level that was tried. Level 1 always runs to completion, even if it takes longer
+ * than `max_time_ms`.
+ */
+export declare function compressLevelAuto(data: Buffer, targetRatio: number, maxTimeMs: number): Buffer
+
+export const enum CompressionFormat {
+  Gzip = 0,
+  Zlib = 1,
+  Deflate = 2,
+  Zstd = 3,
+  Brotli = 4,
+  Lz4 = 5,
+  Snappy = 6
+}
+export interface ProbeResult {
+  /** The detected format, or absent if nothing matched */
+  format?: CompressionFormat
+  /** 1.0 for magic-byte matches, 0.5 for probable matches and 0.0 when nothing matched */
+  confidence: number
+  /** The window bits to hand to zlib for this stream, if it is a zlib-family format */
+  windowBits?: number
+}
+/**
+ * Guesses the compression format of `data` by looking at its first bytes.
+ *
+ * Brotli streams carry no magic bytes and cannot be told apart from arbitrary data, so they are never reported.
+ */
+export declare function probeFormat(data: Buffer): ProbeResult
+/**
+ * Checks whether `data` is, or starts like, a valid zlib stream.
+ *
+ * The two-byte header must pass its check bits and declare deflate with a window of at most 32 KiB. If the first
+ * deflate block header is there too, it must not use the reserved block type. If the whole stream is there, it is
+ * inflated into a scratch buffer (without keeping the output) and the Adler-32 trailer must match the checksum of
+ * what it inflates to. That check is skipped for streams using a preset dictionary, which can't be inflated without
+ * it, and for streams that are cut short.
+ */
+export declare function isValidZlib(data: Buffer): boolean
+/**
+ * Checks whether `data` starts with a complete and valid gzip member header, without decompressing it.
+ *
+ * Besides the magic bytes, the compression method must be deflate and no reserved flags may be set. The optional
+ * extra field, file name and comment must all be there, and the header CRC must match if there is one.
+ */
+export declare function isValidGzip(data: Buffer): boolean
+/**
+ * Combines the Adler-32 checksums of two consecutive pieces of data into the checksum of both (zlib's
+ * `adler32_combine`), where `len2` is the length of the second piece.
+ *
+ * This lets large inputs be checksummed in parallel, one piece at a time, without going over all of the data again.
+ */
+export declare function adler32Combine(adler1: number, adler2: number, len2: number): number
+
+/**
+ * Result of pushing data into a decompressor or compressor.
+ *
+ * `data` is always present, and is `null` when nothing was produced. `ok` results also carry `bytesConsumed`, while
+ * failed results carry `error`.
+ */
+export interface PushResult {
+  ok: boolean
+  bytesConsumed?: number
+  data: Buffer | null
+  error?: string
+}
+/**
+ * Result of finishing a stream.
+ *
+ * `data` holds whatever output was still pending, or `null` if there was none. Failed results carry `error`.
+ */
+export interface FinishResult {
+  ok: boolean
+  data: Buffer | null
+  error?: string
+}
+export interface StreamStats {
+  totalIn: number
+  totalOut: number
+  adler: number
+  /** `totalOut / totalIn`, or 0 if nothing has been read yet */
+  ratio: number
+}
+export interface SyncResult {
+  /** Whether a flush point was found */
+  ok: boolean
+  /** How many bytes of corrupted input were skipped to get there */
+  bytesSkipped: number
+}
+/**
+ * Compresses every item independently off the main thread, spreading the work across a thread pool.
+ *
+ * The resolved array preserves the order of `items`. Only the zlib-family formats (gzip, zlib and raw deflate)
+ * are supported.
+ */
+export declare function compressBatchAsync(items: Array<Buffer>, format: CompressionFormat, level: number): Promise<Array<Buffer>>
+
+/** Metadata for the header of a gzip-wrapped stream (RFC 1952). */
+export interface GzipHeader {
+  /** Whether the data is believed to be text (FTEXT), defaults to `false` */
+  text?: boolean
+  /** Modification time of the original data in seconds since the Unix epoch, defaults to 0 (unknown) */
+  mtime?: number
+  /** Code of the operating system the data was compressed on, defaults to the current one (3 for Unix) */
+  os?: number
+  /** The extra field (FEXTRA), up to 65535 bytes */
+  extra?: Buffer
+  /** The original file name (FNAME), which can't contain NUL characters */
+  name?: string
+  /** A comment (FCOMMENT), which can't contain NUL characters */
+  comment?: string
+  /** Whether the header is followed by a CRC-16 of itself (FHCRC), defaults to `false` */
+  headerCrc?: boolean
+}
+/** How deflate goes about finding matches (zlib's `strategy` parameter). */
+export const enum CompressionStrategy {
+  /** Regular LZ77 matching followed by Huffman coding */
+  Default = 0,
+  /** Favors Huffman coding over short matches, for data made of small, somewhat random values */
+  Filtered = 1,
+  /** Huffman coding only, without any matching */
+  HuffmanOnly = 2,
+  /** Only matches runs of the same byte (distance 1), as for image data such as PNG */
+  Rle = 3,
+  /** Uses the fixed Huffman codes instead of building dynamic ones, for small inputs */
+  Fixed = 4
+}
+export const enum FlushMode {
+  /** Flush all pending output, without aligning it to a byte boundary */
+  Partial = 0,
+  /** Flush all pending output and align it to a byte boundary (the usual `Z_SYNC_FLUSH`) */
+  Sync = 1,
+  /** Like `Sync`, but also resets the compression state so decompression can restart from this point */
+  Full = 2,
+  /** Complete the stream, writing its trailer */
+  Finish = 3
+}
+/**
+ * Duplicates the state of `source` (zlib's `deflateCopy`), so compression can carry on from the same point in two
+ * different ways, for example to find out which flush point compresses better.
+ *
+ * The copy has the same chunk size and finished state, and from then on is completely independent of `source`:
+ * pushing the same data into both produces identical output.
+ */
+export declare function deflateCopy(source: DeflateCompressor): DeflateCompressor
+/**
+ * Upper bound on the size of a gzip-wrapped stream compressed from `inputLen` bytes in one go.
+ *
+ * This assumes the default header. Fields set through `setHeader` make it bigger: the extra field by its length plus
+ * 2, the name and comment by their length plus 1 and the header CRC by 2.
+ */
+export declare function compressBoundGzip(inputLen: number): number
+/**
+ * Decompresses every back-to-back stream in `data` separately, such as the members of a multi-member gzip file.
+ *
+ * Only the zlib-family formats (gzip, zlib and raw deflate) are supported.
+ */
+export declare function decompressAllFrames(data: Buffer, format: CompressionFormat): Array<Buffer>
+/**
+ * Inflates a complete zlib stream off the main thread, so large inputs don't block the event loop.
+ *
+ * `data` is copied before the task is queued, so the caller's buffer can be reused right away.
+ */
+export declare function decompressAllAsync(data: Buffer): Promise<Buffer>
+/**
+ * Async version of `compressDeflate`, deflating `data` into a zlib-wrapped stream off the main thread.
+ *
+ * `level` defaults to 6 and is validated right away, so an invalid level throws instead of rejecting. `data` is
+ * copied before the task is queued.
+ */
+export declare function compressAllAsync(data: Buffer, level?: number | undefined | null): Promise<Buffer>
+/** Options for `ZlibDecompressor`, as an alternative to its positional constructor arguments. */
+export interface DecompressorOptions {
+  /** Size of the chunks the output is produced in, defaults to 16 KiB */
+  chunkSize?: number
+  /** The window bits inflate is initialized with, defaults to 15 (zlib-wrapped, 32 KiB window) */
+  windowBits?: number
+  /** The preset dictionary the stream was compressed with, if any */
+  dictionary?: Buffer
+  /**
+   * How many bytes a single call may produce. Going over it fails with `Output size limit exceeded` and finishes
+   * the decompressor, as a defense against decompression bombs
+   */
+  maxOutputSize?: number
+}
+/** Options for `DeflateCompressor`, as an alternative to its positional constructor arguments. */
+export interface CompressorOptions {
+  /** Size of the chunks the output is produced in, defaults to 16 KiB */
+  chunkSize?: number
+  /** Compression level within 0-9, defaults to 6 */
+  level?: number
+  /**
+   * The window bits deflate is initialized with, defaults to 15 (zlib-wrapped, 32 KiB window). -15 to -9 produce
+   * raw deflate and 25-31 gzip-wrapped streams
+   */
+  windowBits?: number
+  /** How deflate looks for matches, defaults to `CompressionStrategy.Default` */
+  strategy?: CompressionStrategy
+}
+export const enum Lz4Format {
+  /** A single block with its uncompressed size prepended, as produced by `lz4_flex::compress_prepend_size` */
+  Block = 0,
+  /** The standard LZ4 frame format, which may contain several back-to-back frames */
+  Frame = 1
+}
+
+/** A zstd error, broken down for callers that want to handle some kinds of errors differently. */
+export interface ZstdError {
+  /** The `ZSTD_ErrorCode`, which is stable across zstd versions */
+  code: number
+  /** zstd's description of the error */
+  name: string
+  /** Whether the input is corrupted, as opposed to being valid but unsupported or running out of resources */
+  isCorruption: boolean
+  /** Whether zstd failed to allocate memory */
+  isOom: boolean
+}
+export const enum ZstdStrategy {
+  Fast = 1,
+  DFast = 2,
+  Greedy = 3,
+  Lazy = 4,
+  Lazy2 = 5,
+  BtLazy2 = 6,
+  BtOpt = 7,
+  BtUltra = 8,
+  BtUltra2 = 9
+}
+/** The parameters zstd derives from a compression level. */
+export interface ZstdCompressionParameters {
+  /** log2 of the largest match distance */
+  windowLog: number
+  /** log2 of the size of the match chain table */
+  chainLog: number
+  /** log2 of the size of the hash table */
+  hashLog: number
+  /** log2 of how many searches are made per position */
+  searchLog: number
+  /** Shortest match length searched for */
+  minMatch: number
+  /** Match length at which the optimal parser stops searching, or the acceleration factor for the fast strategy */
+  targetLength: number
+  strategy: ZstdStrategy
+}
+/**
+ * Trains a zstd dictionary of up to `dict_size` bytes from `samples`, which should be representative of the data it
+ * will be used on (for example individual messages rather than whole streams).
+ *
+ * The result can be passed to both `ZstdCompressor` and `ZstdDecompressor`. zstd needs a reasonable number of
+ * samples to work with, and fails if there are too few or they are too small.
+ */
+export declare function trainZstdDictionary(samples: Array<Buffer>, dictSize: number): Buffer
+export const enum BrotliMode {
+  /** No assumptions about the input */
+  Generic = 0,
+  /** UTF-8 text */
+  Text = 1,
+  /** WOFF 2.0 font data */
+  Font = 2
+}
+/**
+ * Estimates the compression ratio (input size divided by compressed size) brotli reaches at `quality` for the given
+ * kind of data, without compressing anything.
+ *
+ * The estimate is the middle of the range measured on sample data, so actual ratios can differ quite a bit from it.
+ */
+export declare function brotliQualityToRatioEstimate(quality: number, dataType: BrotliMode): number
+export declare class BrotliCompressor {
+  /** Creates a brotli compressor. `quality` must be within 0-11 and `lgwin` (the log2 of the window size) within 10-24. */
+  constructor(chunkSize: number, quality: number, lgwin: number)
+  /** Feeds `data` into the compressor. The encoder may hold on to input, so `data` may be `null` until `finish`. */
+  push(data: Uint8Array): PushResult
+  /** Completes the stream, returning everything the encoder was still holding on to. */
+  finish(): FinishResult
+  /**
+   * Wraps `data` in a metadata block, which decoders skip over without producing any output. Useful for embedding
+   * out-of-band information such as version tags in the stream.
+   *
+   * Anything the encoder was holding on to is flushed first, so the returned buffer is everything to send next.
+   * Metadata blocks hold at most 16 MiB.
+   */
+  writeMetadataBlock(data: Buffer): Buffer
+  /**
+   * Frees the encoder state right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Compressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class BrotliDecompressor {
   constructor(chunkSize: number)
-  push(data: Buffer): { ok: true; data?: Buffer; } | { ok: false; error: string }
+  /** Decompresses as much of `data` as possible. */
+  push(data: Uint8Array): PushResult
+  /**
+   * Whether the stream uses the large-window extension, which allows windows of up to 1 GiB.
+   *
+   * This is `false` until the stream header has been read.
+   */
+  get usedLargeWindow(): boolean
+  /**
+   * How many bytes of output `push` has handed out since the decompressor was created.
+   *
+   * Kept as a 64-bit count, but exposed as a number, which is exact up to 2^53 bytes (8 PiB).
+   */
+  get bytesDecompressed(): number
+  /** Checks that the stream was complete. All output is already handed out by `push`, so `data` is always `null`. */
+  finish(): FinishResult
+  /**
+   * Starts over on a new stream, as if this were a new decompressor with the same chunk size, so decompressors can
+   * be pooled and reused once a stream has ended.
+   *
+   * Whatever was left of the previous stream is dropped, along with `usedLargeWindow` and `bytesDecompressed`. The
+   * decoder state is reinitialized inside its existing allocation, while its window and ring buffer are only
+   * allocated again once the next stream needs them.
+   */
+  restart(): void
+  /**
+   * Frees the decoder state right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class DeflateCompressor {
+  /**
+   * Creates a deflate compressor from a chunk size and optional level, or from a `CompressorOptions` object.
+   * `level` defaults to 6 and the chunk size to 16 KiB. Streams are zlib-wrapped unless `windowBits` says otherwise.
+   */
+  constructor(options?: number | CompressorOptions | undefined | null, level?: number | undefined | null)
+  /**
+   * The window bits the deflate stream uses, which determine its window size and whether it is zlib-wrapped (9-15),
+   * raw (-15 to -9) or gzip-wrapped (25-31). Streams created with 8 window bits report 9, since deflate raises
+   * them to that.
+   */
+  get windowBits(): number
+  /**
+   * Sets the metadata written into the gzip header (zlib's `deflateSetHeader`), such as the file name, comment,
+   * modification time and extra field.
+   *
+   * Only gzip-wrapped streams (window bits 25-31) have a header, and it has to be set before any output has been
+   * produced. Otherwise this throws. Setting it again before then replaces the previous header.
+   */
+  setHeader(header: GzipHeader): void
+  /**
+   * Upper bound on the size of the stream this compressor produces from `inputLen` bytes compressed in one go (zlib's
+   * `deflateBound`), for sizing output buffers up front.
+   *
+   * Unlike `compressBoundGzip`, this goes by the stream's own settings: its wrapper and the header set through
+   * `setHeader`, and its window bits and level, which allow a tighter bound for some streams.
+   */
+  compressBound(inputLen: number): number
+  /**
+   * Feeds `data` into the compressor without flushing.
+   *
+   * deflate is free to hold on to input to improve compression, so `data` may well be `null` until the stream is
+   * flushed or finished.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Flushes everything deflate is holding on to.
+   *
+   * With `FlushMode.Finish` this keeps calling deflate until the stream ends, so the output is never truncated, and
+   * the compressor can't be used afterwards. The other modes leave the stream open for more data.
+   */
+  finish(flush: FlushMode): PushResult
+  /**
+   * Feeds every buffer in `chunks` into the compressor in order, then flushes with `flush`, returning all of the
+   * output at once.
+   *
+   * Equivalent to calling `push` for each chunk followed by `finish(flush)`, but in a single call. Failures are
+   * thrown, and leave the compressor finished just like with `push`.
+   */
+  compressMany(chunks: Array<Buffer>, flush: FlushMode): Buffer
+  /**
+   * Writes `data` into the stream as stored (uncompressed) blocks, without attempting to compress it.
+   *
+   * Meant for mixed-content streams, where already-compressed or encrypted segments would only grow if deflated.
+   * Anything deflate was holding on to is flushed first, and the stream goes back to its own level afterwards.
+   * Returns the output produced along the way.
+   */
+  writeStoredBlock(data: Buffer): Buffer
+  /**
+   * Builds the 8-byte gzip trailer: the CRC-32 of the uncompressed data followed by its size modulo 2^32
+   * (ISIZE), both little-endian.
+   *
+   * Meant for callers assembling gzip members by hand around raw deflate data. Both values must fit in 32 bits.
+   */
+  static writeGzipFooter(crc32: number, isize: number): Buffer
+  /**
+   * Tears the deflate stream down right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Compressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class HttpDecompressor {
+  /**
+   * Creates a decompressor undoing the codings listed in a `Content-Encoding` header, such as `gzip` or
+   * `gzip, br`.
+   *
+   * Codings are undone in the reverse of the order they are listed in. `gzip`, `x-gzip`, `deflate` and `identity`
+   * are always supported, while `br` and `zstd` need the matching features. Any other coding is rejected.
+   */
+  static newForHttpResponse(contentEncoding: string): HttpDecompressor
+  /**
+   * Decompresses as much of `data` as possible, passing it through every coding in turn.
+   *
+   * `bytesConsumed` is how much of `data` the first coding consumed. Once a push has failed, every later one fails
+   * with the same error.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Checks that every coding reached the end of its stream, returning any output that was still pending.
+   *
+   * Fails with the error that broke the body if a push or an earlier finish failed.
+   */
+  finish(): FinishResult
+  /**
+   * Decompresses the rest of the body, `data`, then finishes it like `finish`, writing the output to the file at
+   * `path` instead of returning it.
+   *
+   * `data` goes through the codings in chunks, and each chunk of output is written as soon as it comes out, so
+   * neither the body nor its output have to fit in memory at once, and none of it crosses over into JavaScript.
+   * Failures to decompress the body, including ones from earlier pushes, or to write the file are thrown, as is
+   * calling this on a body that was already finished or disposed of.
+   *
+   * The file is only created (or truncated) once there is output to write, or once an empty body has finished, so
+   * a body that fails before producing anything leaves an existing file alone.
+   */
+  writeToFile(path: string, data?: Uint8Array | undefined | null): void
+  /**
+   * Tears down the decoder of every coding right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class ZlibDecompressor {
+  /**
+   * Creates a zlib decompressor from a chunk size and optional window bits, or from a `DecompressorOptions`
+   * object. Without arguments, a 16 KiB chunk size and 15 window bits are used.
+   */
+  constructor(options?: number | DecompressorOptions | undefined | null, windowBits?: number | undefined | null)
+  /**
+   * Creates a zlib decompressor whose LZ77 window holds at least `n_bytes` bytes of history.
+   *
+   * The size is rounded up to the next valid window size (a power of two between 256 bytes and 32 KiB), so
+   * `n_bytes` must be within 1-32768.
+   */
+  static withLz77HistorySize(chunkSize: number, nBytes: number): ZlibDecompressor
+  /**
+   * Creates a zlib decompressor that detects whether the stream is zlib or gzip-wrapped by itself (47 window bits).
+   *
+   * The wrapper is told apart by the first two bytes of the stream, which inflate waits for if they are split
+   * across calls. Raw deflate streams can't be detected and fail as invalid.
+   */
+  static autoDetect(chunkSize?: number | undefined | null): ZlibDecompressor
+  /**
+   * Switches the stream over to `bits` window bits, for protocols that negotiate them after the decompressor was
+   * created.
+   *
+   * This resets the stream (reusing its allocations), so any buffered input and history is discarded and a
+   * finished decompressor becomes usable again.
+   */
+  setWindowBits(bits: number): void
+  /**
+   * Sets how many bytes a single call may produce, like the `maxOutputSize` option. Takes effect from the next
+   * call on.
+   */
+  setOutputLimit(bytes: number): void
+  /** Removes the output limit, if there was one. */
+  clearOutputLimit(): void
+  /**
+   * Buffers `data` until a full sync-flushed message is available, then inflates it.
+   *
+   * `bytesConsumed` is how many buffered bytes inflate consumed during this call. It is 0 while a message is still
+   * being buffered. `data` is always copied, so the caller's buffer can be reused as soon as this returns.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Like `push`, but takes a whole `ArrayBuffer` or `SharedArrayBuffer` rather than a view into one.
+   *
+   * N-API can't read a `SharedArrayBuffer` directly, so it is wrapped in a `Uint8Array` first. Just like with
+   * `push`, the data is copied before inflating, so inflate never reads from memory other threads may be writing to.
+   */
+  pushShared(data: ArrayBuffer | SharedArrayBuffer): PushResult
+  /**
+   * Inflates the next complete sync-flushed message still sitting in the internal buffer, or returns `null` if
+   * more data is needed.
+   *
+   * `push` only inflates once the buffer ends with the sync flush suffix, so a push carrying one message and the
+   * start of the next leaves the complete one buffered. This pulls such messages out one at a time, returning an
+   * empty buffer for messages that inflate to nothing. Inflate failures throw and finish the decompressor.
+   *
+   * The suffix bytes can also turn up inside compressed data, so a suffix in the middle of the buffer only ends a
+   * message if inflating up to it leaves the stream between two blocks, which is checked on a copy of the stream.
+   */
+  decompressFrame(): Buffer | null
+  /**
+   * Like `push`, but gives up on the stream once its running expansion ratio (`totalOut / totalIn`) goes above
+   * `maxRatio`, as a defense against decompression bombs.
+   *
+   * The ratio is checked after every inflate call, so at most one `chunkSize` worth of output is produced past the
+   * limit. Exceeding it fails with `ratio limit exceeded` and finishes the decompressor.
+   */
+  safeDecompress(data: Uint8Array, maxRatio: number): PushResult
+  /**
+   * How long the last `push` or `decompressFrame` call spent inflating, in nanoseconds. Calls that only buffered
+   * their input report 0.
+   */
+  get lastPushDurationNs(): number
+  /**
+   * How many sync-flushed messages `push`, `safeDecompress` and `decompressFrame` have inflated successfully, for
+   * tracking throughput.
+   */
+  get framesProcessed(): number
+  /**
+   * Inflates a message that arrived in several fragments (e.g. fragmented WebSocket frames) in one go.
+   *
+   * The fragments are fed to inflate one after another, after anything already buffered by `push`, without being
+   * concatenated first. Unlike `push`, no sync-flush suffix is required and failures are thrown.
+   */
+  inflateCoalesce(data: Array<Buffer>): Buffer
+  /** How many bytes are buffered while waiting for a sync-flush suffix. */
+  get inputBufferLen(): number
+  /**
+   * Discards the input buffered while waiting for a sync-flush suffix, leaving the stream itself untouched.
+   *
+   * Meant for when the framing layer knows a message was cut short, such as by a dropped connection, but the
+   * stream is still intact, so the partial message doesn't end up in front of the next one.
+   */
+  clearBuffer(): void
+  /**
+   * Where the sync-flush suffix started in the last complete message `push`, `safeDecompress` or `decompressFrame`
+   * inflated, or `null` if there hasn't been one yet.
+   *
+   * Messages always end with the suffix, so this is the message length minus 4.
+   */
+  get suffixPosition(): number | null
+  /**
+   * How much input the last inflate call left unconsumed (the stream's `avail_in`).
+   *
+   * This is usually 0. Anything else means inflate stopped early, typically because the stream ended with more
+   * data behind it, such as a second concatenated stream, or because it hit an error.
+   */
+  get inputRemaining(): number
+  /** The message zlib reported for the last inflate error, if any. */
+  get lastErrorMessage(): string | null
+  /**
+   * Checks whether pushing `data` would succeed, without modifying this decompressor.
+   *
+   * The pending buffered input plus `data` is inflated through a copy of the stream, regardless of whether it ends
+   * with a sync-flush suffix yet. Returns `false` if the decompressor has already finished.
+   */
+  testPush(data: Uint8Array): boolean
+  /**
+   * The decompressor's current state as a single-line JSON object, for logging.
+   *
+   * Holds `totalIn`, `totalOut`, `ratio`, `finished`, `error` (the last inflate error message, or `null`),
+   * `chunkSize` and `windowBits`.
+   */
+  metricsJson(): string
+  /**
+   * Whether `other` is configured the same way as this decompressor, with the same window bits and chunk size, and
+   * neither of them has finished or hit an error.
+   *
+   * Meant for pools checking that an idle decompressor fits a request before handing it out.
+   */
+  compatibleWith(other: ZlibDecompressor): boolean
+  /**
+   * A human-readable dump of the decompressor's internal state, for crash reports.
+   *
+   * Lists the addresses of the stream and the buffers it last worked with next to its counters, as well as the
+   * buffered input length, whether the decompressor finished and the last inflate error. Pointers are only printed,
+   * never followed, so this is safe to call at any time, including after an error.
+   */
+  createDebugDump(): string
+  /**
+   * Skips ahead to the next flush point (the empty stored block written by a sync or full flush) in corrupted
+   * input, so decompression can pick up again from there (`inflateSync`).
+   *
+   * The search covers any buffered input followed by `data`. Input after the flush point stays buffered for the
+   * next `push`, and a decompressor that finished because of an error becomes usable again. If no flush point is
+   * found, all of the input is skipped and the search carries on with the next call. History from before the
+   * corruption is kept, so output from blocks referencing it can still be wrong.
+   */
+  inflateSync(data: Buffer): SyncResult
+  /** Snapshot of the stream's counters, read in a single call. */
+  stats(): StreamStats
+  /**
+   * Tears the inflate stream down right away instead of waiting for the garbage collector, and drops any buffered
+   * input.
+   *
+   * From then on `push` and `safeDecompress` fail with `Decompressor has been disposed`, and the methods that throw
+   * on failure throw it instead. Disposing more than once does nothing.
+   */
+  dispose(): void
+}
+export declare class ZstdDecompressor {
+  /**
+   * Creates a zstd decompressor, optionally loaded with the `dictionary` the frames were compressed with.
+   *
+   * The dictionary can be a trained zstd dictionary or raw content, and stays loaded for every frame that follows.
+   */
+  constructor(chunkSize: number, dictionary?: Buffer | undefined | null)
+  /**
+   * Decompresses as much of `data` as possible.
+   *
+   * Back-to-back frames are decompressed one after the other, so the stream only counts as complete once the last
+   * frame pushed so far has ended.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Switches between regular frames and frames with their 4 magic bytes stripped, as used by some custom framings.
+   *
+   * Magic-less frames can't be told apart from garbage, so only enable this when the framing is known for sure.
+   * Must be called before the first `push`, or between frames.
+   */
+  allowMagicLess(enable: boolean): void
+  /** The error that made `push` fail, broken down into its zstd error code and kind, or `null` if there was none. */
+  get structuredError(): ZstdError | null
+  /**
+   * How many bytes the last `push` call produced, as opposed to the running total. Useful for spotting chunks that
+   * decompress to suspiciously large outputs.
+   */
+  get decompressedBytesThisCall(): number
+  /**
+   * Checks that the stream did not end in the middle of a frame. All output is already handed out by `push`, so
+   * `data` is always `null`.
+   *
+   * A stream that `push` already failed on fails again here with the same error.
+   */
+  finish(): FinishResult
+  /**
+   * Frees the decompression context right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Decompressor has been disposed`, and `allowMagicLess` throws it.
+   * Disposing more than once does nothing.
+   */
+  dispose(): void
+}
+export declare class ZstdCompressor {
+  /**
+   * Creates a zstd compressor. `level` must be within -5-22, where negative levels trade ratio for speed.
+   *
+   * With a `dictionary`, every frame is compressed against it, and the decompressor must be given the exact same
+   * dictionary to read them back.
+   */
+  constructor(chunkSize: number, level: number, dictionary?: Buffer | undefined | null)
+  /**
+   * Compresses `data` into a single complete zstd frame on a worker thread, so large inputs don't block the event
+   * loop.
+   *
+   * `level` is validated right away like the constructor's, so an invalid level throws instead of rejecting.
+   * `data` is copied before the task is queued.
+   */
+  static compressInWorker(data: Buffer, level: number): Promise<Buffer>
+  /** The parameters zstd resolves this compressor's level to, assuming the input size is unknown. */
+  getCompressionParameters(): ZstdCompressionParameters
+  /**
+   * Declares the total size of the input up front, which lets zstd pick better parameters and record the size in
+   * the frame header.
+   *
+   * Must be called before the first `push`. Finishing the stream fails if the pushed input doesn't add up to `size`.
+   */
+  setPledgedSrcSize(size: number): void
+  /**
+   * Sets how much of the window each job of multi-threaded compression shares with the previous one
+   * (`ZSTD_c_overlapLog`), within 0-9. 0 lets zstd pick based on the strategy, 1 means no overlap and 9 a full
+   * window, with each step in between doubling it. A larger overlap improves the ratio at the cost of more memory.
+   *
+   * This only takes effect when zstd compresses with 2 or more workers (`nbWorkers >= 2`). This compressor doesn't
+   * enable any, so on its own the setting has no effect on the output.
+   */
+  setOverlapLog(log: number): void
+  /** Feeds `data` into the compressor. The encoder may hold on to input, so `data` may be `null` until `finish`. */
+  push(data: Uint8Array): PushResult
+  /**
+   * Builds a complete zstd frame with no content, typically 9 bytes long, for protocols that send empty frames as
+   * keep-alives or checkpoints.
+   *
+   * The frame is independent of this compressor's own stream, which is left untouched. A zstd stream is a sequence
+   * of frames, so the empty frame can go before this compressor's output or after `finish`, but not in the middle
+   * of a frame.
+   */
+  writeEmptyBlock(): Buffer
+  /** Completes the frame, returning everything the encoder was still holding on to. */
+  finish(): FinishResult
+  /**
+   * Frees the compression context right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Compressor has been disposed`, and `setPledgedSrcSize`,
+   * `setOverlapLog` and `writeEmptyBlock` throw it. Disposing more than once does nothing.
+   */
+  dispose(): void
+}
+export declare class Lz4Decompressor {
+  /**
+   * Creates a decompressor for the given format.
+   *
+   * `maxBlockSize` caps the uncompressed size a `Lz4Format.Block` block may declare, defaulting to 64 MiB, since
+   * that much is allocated up front when it is decoded. Frames are only held to the maximum block size in their
+   * header.
+   */
+  constructor(format: Lz4Format, maxBlockSize?: number | undefined | null)
+  /**
+   * Decompresses every frame of the input that has fully arrived.
+   *
+   * LZ4 frames are decoded whole, so input is buffered until the end of a frame is available. `bytesConsumed` is
+   * how many buffered bytes were decoded during this call. Blocks larger than the maximum declared in their
+   * frame's header fail as soon as their size arrives. Blocks whose block checksum doesn't match are decoded anyway
+   * and counted in `blockChecksumErrors`.
+   *
+   * In `Lz4Format.Block` mode the block's compressed length isn't known up front, so everything is buffered and
+   * only decoded by `finish`. Blocks declaring more than `maxBlockSize` bytes fail right away, as does input that
+   * grows past the largest a block of the declared size can compress to.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * How many frame blocks were decoded despite a block checksum that didn't match them, so callers can decide how
+   * much corruption to put up with.
+   *
+   * Such blocks are still decoded, so corrupted data may make decoding fail or come out wrong. If the frame has a
+   * content checksum, it still fails the frame when the output is wrong.
+   */
+  get blockChecksumErrors(): number
+  /**
+   * Checks that the stream did not end in the middle of a frame.
+   *
+   * In `Lz4Format.Block` mode this is where the buffered block gets decoded, so `data` holds the whole output.
+   * In `Lz4Format.Frame` mode all output is already handed out by `push`, so `data` is always `null`.
+   */
+  finish(): FinishResult
+  /**
+   * Drops the buffered input right away instead of waiting for the garbage collector.
+   *
+   * From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class SnappyDecompressor {
+  /**
+   * Creates a snappy decompressor.
+   *
+   * `maxBlockSize` caps the uncompressed size a block may declare in its header, defaulting to 64 MiB, since that
+   * much is allocated up front when it is decoded.
+   */
+  constructor(maxBlockSize?: number | undefined | null)
+  /**
+   * Decompresses `data` as one complete raw snappy block.
+   *
+   * Snappy is a block format with no framing of its own, so every `push` must hold exactly one whole block, and
+   * its output is returned right away. `bytesConsumed` is always the length of `data`. Blocks declaring more than
+   * `maxBlockSize` bytes fail before anything is allocated for them.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Blocks are decoded as soon as they are pushed, so there is never anything pending and `data` is always
+   * `null`.
+   */
+  finish(): FinishResult
+  /**
+   * Makes the decompressor usable again after a stream has ended or a block failed to decode, keeping its
+   * `maxBlockSize`.
+   *
+   * The raw snappy decoder keeps no state or buffers between blocks, since each block's output is allocated for it
+   * and handed out by `push`. There is no allocation to keep or free, so resetting only clears the finished state.
+   */
+  reset(): void
+  /**
+   * Marks the decompressor as unusable. The decoder holds no resources of its own, so this only exists for parity
+   * with the other decompressors.
+   *
+   * From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+   * nothing.
+   */
+  dispose(): void
+}
+export declare class SnappyCompressor {
+  constructor()
+  /**
+   * Creates a snappy compressor whose pushes may produce at most `maxOutputBytes` bytes between them, for
+   * protocols with a hard limit on the size of a payload.
+   *
+   * A `push` that could take the total past the budget fails with `budget exceeded` before anything is compressed.
+   * Since that is decided up front, it goes by the largest size the block could compress to, so a push may be
+   * refused even though its actual output would have fit. Refused pushes leave the compressor as it was, so
+   * smaller ones can still go through.
+   */
+  static newWithBudget(maxOutputBytes: number): SnappyCompressor
+  /**
+   * Limits how many bytes of input a single block may hold, such as to keep blocks within the `maxBlockSize` of the
+   * `SnappyDecompressor` on the other end, or to keep them small for latency.
+   *
+   * Every `push` compresses a whole block at once, so there is never a block in progress and the new size applies
+   * from the next `push` on.
+   */
+  setChunkSize(chunkSize: number): void
+  /**
+   * Compresses `data` into one raw snappy block.
+   *
+   * Snappy is block-oriented, so every `push` produces a complete block that can be decompressed on its own, with
+   * no state carried over between calls and nothing to flush at the end. `bytesConsumed` is always the length of
+   * `data`. Once `setChunkSize` was called, `data` larger than the chunk size fails instead, as does `data` that
+   * could go over the budget of a compressor created by `newWithBudget`.
+   */
+  push(data: Uint8Array): PushResult
+  /**
+   * Marks the compressor as unusable. The encoder holds no resources of its own, so this only exists for parity
+   * with the other compressors.
+   *
+   * From then on `push` fails with `Compressor has been disposed`. Disposing more than once does nothing.
+   */
+  dispose(): void
+}
+export declare class MultiFormatDecompressor {
+  constructor()
+  /**
+   * Decompresses `data` without knowing its format up front, for responses that come without a
+   * `Content-Encoding` header.
+   *
+   * gzip is tried first if `data` starts with a valid gzip header, then zlib if it starts with a valid zlib header,
+   * and finally raw deflate. Each attempt uses a fresh inflate stream, and the output of the first one that
+   * decompresses all of `data` is returned. Back-to-back streams (such as gzip members) are concatenated.
+   */
+  decompress(data: Buffer): Buffer
+  /** The format the last successful `decompress` call detected, or `null` if there hasn't been one yet. */
+  get lastFormat(): CompressionFormat | null
+}
+/**
+ * Result of pushing data into a `TextDecompressor`.
+ *
+ * `text` is only there once a message was inflated, and `finished` tells whether the underlying stream has ended or
+ * hit a terminal error. Failed results carry `error`.
+ */
+export interface TextPushResult {
+  ok: boolean
+  text?: string
+  finished: boolean
+  error?: string
+}
+export declare class TextDecompressor {
+  /**
+   * Creates a zlib decompressor that hands out each inflated message as a UTF-8 string, taking the same arguments
+   * as `ZlibDecompressor`.
+   */
+  constructor(options?: number | DecompressorOptions | undefined | null, windowBits?: number | undefined | null)
+  /**
+   * Like `ZlibDecompressor.push`, but decodes the inflated message as UTF-8 instead of returning it as a `Buffer`.
+   *
+   * A message that isn't valid UTF-8 fails with `Invalid UTF-8` and is dropped, while the stream itself carries on
+   * with the next one.
+   */
+  push(data: Uint8Array): TextPushResult
+  /** Tears down the underlying `ZlibDecompressor`, see `ZlibDecompressor.dispose`. */
+  dispose(): void
+}
+/**
+ * Result of pushing data into a `JsonDecompressor`.
+ *
+ * `value` is only there once a message was inflated, and `finished` tells whether the underlying stream has ended or
+ * hit a terminal error. Failed results carry `error`.
+ */
+export interface JsonPushResult {
+  ok: boolean
+  value?: unknown
+  finished: boolean
+  error?: string
+}
+export declare class JsonDecompressor {
+  /**
+   * Creates a zlib decompressor that parses each inflated message as JSON, taking the same arguments as
+   * `ZlibDecompressor`.
+   */
+  constructor(options?: number | DecompressorOptions | undefined | null, windowBits?: number | undefined | null)
+  /**
+   * Like `TextDecompressor.push`, but also parses the message as JSON, saving the `JSON.parse` call on the JS side.
+   *
+   * A message that isn't valid UTF-8 or JSON fails with `Invalid UTF-8` or `Invalid JSON` and is dropped, while the
+   * stream itself carries on with the next one.
+   */
+  push(data: Uint8Array): JsonPushResult
+  /** Tears down the underlying `ZlibDecompressor`, see `ZlibDecompressor.dispose`. */
+  dispose(): void
 }
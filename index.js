@@ -310,6 +310,41 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { ZlibDecompressor } = nativeBinding
+const { decompressHexSync, ZlibDecompressor, compressDeflate, CompressionFormat, probeFormat, compressBatchAsync, DeflateCompressor, FlushMode, decompressAllFrames, BrotliDecompressor, BrotliCompressor, ZstdDecompressor, ZstdCompressor, Lz4Format, Lz4Decompressor, BrotliMode, brotliQualityToRatioEstimate, ZstdStrategy, HttpDecompressor, isValidZlib, isValidGzip, compressLevelAuto, deflateCopy, decompressAllAsync, compressAllAsync, SnappyDecompressor, SnappyCompressor, trainZstdDictionary, MultiFormatDecompressor, CompressionStrategy, compressBoundGzip, TextDecompressor, JsonDecompressor, adler32Combine, deflateHuffmanOnlyBlock, huffmanOnlyCompressAll } = nativeBinding
 
+module.exports.decompressHexSync = decompressHexSync
 module.exports.ZlibDecompressor = ZlibDecompressor
+module.exports.compressDeflate = compressDeflate
+module.exports.CompressionFormat = CompressionFormat
+module.exports.probeFormat = probeFormat
+module.exports.compressBatchAsync = compressBatchAsync
+module.exports.DeflateCompressor = DeflateCompressor
+module.exports.FlushMode = FlushMode
+module.exports.decompressAllFrames = decompressAllFrames
+module.exports.BrotliDecompressor = BrotliDecompressor
+module.exports.BrotliCompressor = BrotliCompressor
+module.exports.ZstdDecompressor = ZstdDecompressor
+module.exports.ZstdCompressor = ZstdCompressor
+module.exports.Lz4Format = Lz4Format
+module.exports.Lz4Decompressor = Lz4Decompressor
+module.exports.BrotliMode = BrotliMode
+module.exports.brotliQualityToRatioEstimate = brotliQualityToRatioEstimate
+module.exports.ZstdStrategy = ZstdStrategy
+module.exports.HttpDecompressor = HttpDecompressor
+module.exports.isValidZlib = isValidZlib
+module.exports.isValidGzip = isValidGzip
+module.exports.compressLevelAuto = compressLevelAuto
+module.exports.deflateCopy = deflateCopy
+module.exports.decompressAllAsync = decompressAllAsync
+module.exports.compressAllAsync = compressAllAsync
+module.exports.SnappyDecompressor = SnappyDecompressor
+module.exports.SnappyCompressor = SnappyCompressor
+module.exports.trainZstdDictionary = trainZstdDictionary
+module.exports.MultiFormatDecompressor = MultiFormatDecompressor
+module.exports.CompressionStrategy = CompressionStrategy
+module.exports.compressBoundGzip = compressBoundGzip
+module.exports.TextDecompressor = TextDecompressor
+module.exports.JsonDecompressor = JsonDecompressor
+module.exports.adler32Combine = adler32Combine
+module.exports.deflateHuffmanOnlyBlock = deflateHuffmanOnlyBlock
+module.exports.huffmanOnlyCompressAll = huffmanOnlyCompressAll
@@ -0,0 +1,458 @@
+use crate::result::{FinishResult, PushResult, COMPRESSOR_DISPOSED, DECOMPRESSOR_DISPOSED};
+use ::brotli::enc::encode::{
+    BrotliEncoderOperation, BrotliEncoderParameter, BrotliEncoderStateStruct,
+};
+use ::brotli::enc::interface::{
+    InputPair, InputReferenceMut, PredictionModeContextMap, StaticCommand,
+};
+use ::brotli_decompressor::{
+    BrotliDecoderIsFinished, BrotliDecompressStream, BrotliResult, BrotliState, StandardAlloc,
+};
+use napi::bindgen_prelude::{Buffer, Result, Status, Uint8Array};
+use napi::Error;
+
+type DecoderState = BrotliState<StandardAlloc, StandardAlloc, StandardAlloc>;
+type EncoderState = BrotliEncoderStateStruct<StandardAlloc>;
+
+// MLEN in a metadata block header is at most 3 bytes long
+const MAX_METADATA_BLOCK_SIZE: usize = 1 << 24;
+
+fn validate_chunk_size(chunk_size: u32) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "Chunk size must be greater than 0",
+        ));
+    }
+
+    Ok(())
+}
+
+#[napi]
+pub enum BrotliMode {
+    /// No assumptions about the input
+    Generic,
+    /// UTF-8 text
+    Text,
+    /// WOFF 2.0 font data
+    Font,
+}
+
+// (min, max) of `input size / compressed size` per quality level, measured with a 4 MiB window on a handful of
+// representative files for each mode: licenses, Python sources and HTML docs for text, executables, shared libraries
+// and a lockfile for generic data, and DejaVu TrueType fonts for fonts.
+const GENERIC_RATIOS: [(f32, f32); 12] = [
+    (1.54, 2.75),
+    (1.60, 3.02),
+    (1.95, 3.66),
+    (1.96, 3.76),
+    (2.01, 3.97),
+    (2.16, 3.90),
+    (2.19, 3.93),
+    (2.22, 4.02),
+    (2.23, 4.03),
+    (2.23, 4.94),
+    (2.39, 5.27),
+    (2.45, 5.52),
+];
+const TEXT_RATIOS: [(f32, f32); 12] = [
+    (2.13, 3.17),
+    (2.12, 3.24),
+    (2.74, 4.98),
+    (2.77, 5.11),
+    (2.89, 5.38),
+    (3.03, 5.77),
+    (3.05, 5.85),
+    (3.05, 5.91),
+    (3.05, 5.94),
+    (3.05, 5.96),
+    (3.53, 6.60),
+    (3.63, 6.74),
+];
+const FONT_RATIOS: [(f32, f32); 12] = [
+    (1.60, 1.98),
+    (1.67, 2.08),
+    (1.93, 2.68),
+    (1.95, 2.70),
+    (2.02, 3.01),
+    (2.16, 3.18),
+    (2.16, 3.20),
+    (2.18, 3.19),
+    (2.18, 3.19),
+    (2.19, 3.21),
+    (2.32, 3.75),
+    (2.37, 3.92),
+];
+
+fn validate_quality(quality: u32) -> Result<()> {
+    if quality > 11 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid brotli quality {}, expected 0-11", quality),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Estimates the compression ratio (input size divided by compressed size) brotli reaches at `quality` for the given
+/// kind of data, without compressing anything.
+///
+/// The estimate is the middle of the range measured on sample data, so actual ratios can differ quite a bit from it.
+#[napi]
+pub fn brotli_quality_to_ratio_estimate(quality: u32, data_type: BrotliMode) -> Result<f32> {
+    validate_quality(quality)?;
+
+    let table = match data_type {
+        BrotliMode::Generic => &GENERIC_RATIOS,
+        BrotliMode::Text => &TEXT_RATIOS,
+        BrotliMode::Font => &FONT_RATIOS,
+    };
+
+    let (min_ratio, max_ratio) = table[quality as usize];
+
+    Ok((min_ratio + max_ratio) / 2.0)
+}
+
+#[napi]
+pub struct BrotliDecompressor {
+    chunk_size: u32,
+    // Boxed since the decoder state is rather large. Taken by dispose
+    state: Option<Box<DecoderState>>,
+    // Track finished state separately (for terminal errors or the end of the stream)
+    finished: bool,
+    // Whether the stream header declared a large window (RFC 7932 section 9), known once the header has been read
+    large_window_used: bool,
+    // Running total of the output handed out by push
+    bytes_decompressed: u64,
+}
+
+#[napi]
+impl BrotliDecompressor {
+    #[napi(constructor)]
+    pub fn new(chunk_size: u32) -> Result<Self> {
+        validate_chunk_size(chunk_size)?;
+
+        Ok(Self {
+            chunk_size,
+            state: Some(Box::new(BrotliState::new(
+                StandardAlloc::default(),
+                StandardAlloc::default(),
+                StandardAlloc::default(),
+            ))),
+            finished: false,
+            large_window_used: false,
+            bytes_decompressed: 0,
+        })
+    }
+
+    /// Decompresses as much of `data` as possible.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        let Some(state) = self.state.as_mut() else {
+            return Ok(PushResult::error(DECOMPRESSOR_DISPOSED));
+        };
+
+        if self.finished {
+            // Already finished (due to error or end of stream), return early
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let mut available_in = data.len();
+        let mut input_offset = 0;
+
+        loop {
+            let mut available_out = temp_out_buf.len();
+            let mut output_offset = 0;
+            let mut total_out = 0;
+
+            let result = BrotliDecompressStream(
+                &mut available_in,
+                &mut input_offset,
+                &data,
+                &mut available_out,
+                &mut output_offset,
+                &mut temp_out_buf,
+                &mut total_out,
+                state,
+            );
+
+            output_buffer.extend_from_slice(&temp_out_buf[..output_offset]);
+
+            match result {
+                // The output chunk was filled, go around again
+                BrotliResult::NeedsMoreOutput => continue,
+                BrotliResult::NeedsMoreInput => break,
+                BrotliResult::ResultSuccess => {
+                    self.finished = true;
+                    break;
+                }
+                BrotliResult::ResultFailure => {
+                    self.finished = true;
+                    return Ok(PushResult::error(format!(
+                        "Brotli error: {:?}",
+                        state.error_code
+                    )));
+                }
+            }
+        }
+
+        // The decoder only keeps large_window set if the window bits it read asked for it, which happens as soon as
+        // the first byte is consumed
+        if input_offset > 0 {
+            self.large_window_used = state.large_window;
+        }
+
+        self.bytes_decompressed += output_buffer.len() as u64;
+
+        Ok(PushResult::ok(input_offset, output_buffer))
+    }
+
+    /// Whether the stream uses the large-window extension, which allows windows of up to 1 GiB.
+    ///
+    /// This is `false` until the stream header has been read.
+    #[napi(getter)]
+    pub fn used_large_window(&self) -> bool {
+        self.large_window_used
+    }
+
+    /// How many bytes of output `push` has handed out since the decompressor was created.
+    ///
+    /// Kept as a 64-bit count, but exposed as a number, which is exact up to 2^53 bytes (8 PiB).
+    #[napi(getter)]
+    pub fn bytes_decompressed(&self) -> f64 {
+        self.bytes_decompressed as f64
+    }
+
+    /// Checks that the stream was complete. All output is already handed out by `push`, so `data` is always `null`.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        let Some(state) = self.state.as_ref() else {
+            return Ok(FinishResult::error(DECOMPRESSOR_DISPOSED));
+        };
+
+        if !BrotliDecoderIsFinished(state) {
+            self.finished = true;
+            return Ok(FinishResult::error("Unexpected end of brotli stream"));
+        }
+
+        self.finished = true;
+        Ok(FinishResult::ok(Vec::new()))
+    }
+
+    /// Starts over on a new stream, as if this were a new decompressor with the same chunk size, so decompressors can
+    /// be pooled and reused once a stream has ended.
+    ///
+    /// Whatever was left of the previous stream is dropped, along with `usedLargeWindow` and `bytesDecompressed`. The
+    /// decoder state is reinitialized inside its existing allocation, while its window and ring buffer are only
+    /// allocated again once the next stream needs them.
+    #[napi]
+    pub fn restart(&mut self) -> Result<()> {
+        let Some(state) = self.state.as_mut() else {
+            return Err(Error::new(Status::GenericFailure, DECOMPRESSOR_DISPOSED));
+        };
+
+        // brotli-decompressor has no way of resetting a state, but the old one frees its buffers when dropped
+        **state = BrotliState::new(
+            StandardAlloc::default(),
+            StandardAlloc::default(),
+            StandardAlloc::default(),
+        );
+
+        self.finished = false;
+        self.large_window_used = false;
+        self.bytes_decompressed = 0;
+
+        Ok(())
+    }
+
+    /// Frees the decoder state right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.state = None;
+        self.finished = true;
+    }
+}
+
+#[napi]
+pub struct BrotliCompressor {
+    chunk_size: u32,
+    // Boxed since the encoder state is rather large. Taken by dispose
+    state: Option<Box<EncoderState>>,
+    // Set once the stream has been finished or hit a terminal error
+    finished: bool,
+}
+
+impl BrotliCompressor {
+    /// Runs `input` through the encoder with `op`, returning how much input was consumed and the output produced,
+    /// or `None` if the encoder failed or was disposed.
+    fn encode(&mut self, op: BrotliEncoderOperation, input: &[u8]) -> Option<(usize, Vec<u8>)> {
+        let state = self.state.as_mut()?;
+
+        let mut nop_callback = |_data: &mut PredictionModeContextMap<InputReferenceMut>,
+                                _cmds: &mut [StaticCommand],
+                                _mb: InputPair,
+                                _alloc: &mut StandardAlloc| ();
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let mut available_in = input.len();
+        let mut input_offset = 0;
+
+        loop {
+            let mut available_out = temp_out_buf.len();
+            let mut output_offset = 0;
+
+            let succeeded = state.compress_stream(
+                op,
+                &mut available_in,
+                input,
+                &mut input_offset,
+                &mut available_out,
+                &mut temp_out_buf,
+                &mut output_offset,
+                &mut None,
+                &mut nop_callback,
+            );
+
+            output_buffer.extend_from_slice(&temp_out_buf[..output_offset]);
+
+            if !succeeded {
+                return None;
+            }
+
+            let done = match op {
+                BrotliEncoderOperation::BROTLI_OPERATION_FINISH => state.is_finished(),
+                _ => available_in == 0 && !state.has_more_output(),
+            };
+
+            if done {
+                return Some((input_offset, output_buffer));
+            }
+        }
+    }
+}
+
+#[napi]
+impl BrotliCompressor {
+    /// Creates a brotli compressor. `quality` must be within 0-11 and `lgwin` (the log2 of the window size) within 10-24.
+    #[napi(constructor)]
+    pub fn new(chunk_size: u32, quality: u32, lgwin: u32) -> Result<Self> {
+        validate_chunk_size(chunk_size)?;
+        validate_quality(quality)?;
+
+        if !(10..=24).contains(&lgwin) {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Invalid brotli window size {}, expected 10-24", lgwin),
+            ));
+        }
+
+        let mut state = Box::new(EncoderState::new(StandardAlloc::default()));
+        state.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_QUALITY, quality);
+        state.set_parameter(BrotliEncoderParameter::BROTLI_PARAM_LGWIN, lgwin);
+
+        Ok(Self {
+            chunk_size,
+            state: Some(state),
+            finished: false,
+        })
+    }
+
+    /// Feeds `data` into the compressor. The encoder may hold on to input, so `data` may be `null` until `finish`.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        if self.state.is_none() {
+            return Ok(PushResult::error(COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Ok(PushResult::error("Compressor has already finished"));
+        }
+
+        match self.encode(BrotliEncoderOperation::BROTLI_OPERATION_PROCESS, &data) {
+            Some((bytes_consumed, output)) => Ok(PushResult::ok(bytes_consumed, output)),
+            None => {
+                self.finished = true;
+                Ok(PushResult::error("Brotli encoder failed"))
+            }
+        }
+    }
+
+    /// Completes the stream, returning everything the encoder was still holding on to.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        if self.state.is_none() {
+            return Ok(FinishResult::error(COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Ok(FinishResult::error("Compressor has already finished"));
+        }
+
+        self.finished = true;
+
+        match self.encode(BrotliEncoderOperation::BROTLI_OPERATION_FINISH, &[]) {
+            Some((_, output)) => Ok(FinishResult::ok(output)),
+            None => Ok(FinishResult::error("Brotli encoder failed")),
+        }
+    }
+
+    /// Wraps `data` in a metadata block, which decoders skip over without producing any output. Useful for embedding
+    /// out-of-band information such as version tags in the stream.
+    ///
+    /// Anything the encoder was holding on to is flushed first, so the returned buffer is everything to send next.
+    /// Metadata blocks hold at most 16 MiB.
+    #[napi]
+    pub fn write_metadata_block(&mut self, data: Buffer) -> Result<Buffer> {
+        if self.state.is_none() {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Compressor has already finished",
+            ));
+        }
+
+        if data.len() > MAX_METADATA_BLOCK_SIZE {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Metadata block of {} bytes is too large, expected at most {}",
+                    data.len(),
+                    MAX_METADATA_BLOCK_SIZE
+                ),
+            ));
+        }
+
+        match self.encode(
+            BrotliEncoderOperation::BROTLI_OPERATION_EMIT_METADATA,
+            &data,
+        ) {
+            Some((_, output)) => Ok(output.into()),
+            None => {
+                self.finished = true;
+                Err(Error::new(Status::GenericFailure, "Brotli encoder failed"))
+            }
+        }
+    }
+
+    /// Frees the encoder state right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Compressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.state = None;
+        self.finished = true;
+    }
+}
@@ -0,0 +1,879 @@
+use crate::oneshot::resolve_level;
+use crate::options::{CompressorOptions, DEFAULT_CHUNK_SIZE};
+use crate::result::{PushResult, COMPRESSOR_DISPOSED};
+use napi::bindgen_prelude::{Buffer, Either, Result, Status, Uint8Array};
+use napi::Error;
+use std::ffi::CString;
+use std::io::{self, Write};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use zlib_rs::{
+    c_api::{gz_header, z_stream},
+    deflate::{self, DeflateConfig, DeflateStream, Strategy},
+    DeflateFlush, ReturnCode,
+};
+
+/// Checks window bits for deflate: 8-15 for zlib-wrapped streams, -15 to -9 for raw ones and 25-31 for gzip-wrapped
+/// ones. Unlike inflate, deflate has no automatic header detection.
+fn validate_deflate_window_bits(window_bits: i32) -> Result<i32> {
+    match window_bits {
+        8..=15 | -15..=-9 | 25..=31 => Ok(window_bits),
+        _ => Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid window bits {}", window_bits),
+        )),
+    }
+}
+
+/// The window bits deflate ends up using for `window_bits`. Like zlib, zlib-rs raises a 256-byte window (8, only
+/// allowed for zlib-wrapped streams) to 512 bytes.
+fn effective_window_bits(window_bits: i32) -> i32 {
+    if window_bits == 8 {
+        9
+    } else {
+        window_bits
+    }
+}
+
+/// Metadata for the header of a gzip-wrapped stream (RFC 1952).
+#[napi(object)]
+#[derive(Default)]
+pub struct GzipHeader {
+    /// Whether the data is believed to be text (FTEXT), defaults to `false`
+    pub text: Option<bool>,
+    /// Modification time of the original data in seconds since the Unix epoch, defaults to 0 (unknown)
+    pub mtime: Option<u32>,
+    /// Code of the operating system the data was compressed on, defaults to the current one (3 for Unix)
+    pub os: Option<u32>,
+    /// The extra field (FEXTRA), up to 65535 bytes
+    pub extra: Option<Buffer>,
+    /// The original file name (FNAME), which can't contain NUL characters
+    pub name: Option<String>,
+    /// A comment (FCOMMENT), which can't contain NUL characters
+    pub comment: Option<String>,
+    /// Whether the header is followed by a CRC-16 of itself (FHCRC), defaults to `false`
+    pub header_crc: Option<bool>,
+}
+
+/// A validated, owned copy of a `GzipHeader`.
+#[derive(Clone)]
+struct GzipHeaderData {
+    text: bool,
+    mtime: u32,
+    os: u8,
+    extra: Option<Vec<u8>>,
+    name: Option<CString>,
+    comment: Option<CString>,
+    header_crc: bool,
+}
+
+impl TryFrom<GzipHeader> for GzipHeaderData {
+    type Error = Error;
+
+    fn try_from(header: GzipHeader) -> Result<Self> {
+        let to_c_string = |field: &str, value: Option<String>| {
+            value
+                .map(|value| {
+                    CString::new(value).map_err(|_| {
+                        Error::new(
+                            Status::InvalidArg,
+                            format!("Gzip header {} can't contain NUL characters", field),
+                        )
+                    })
+                })
+                .transpose()
+        };
+
+        let os = match header.os {
+            Some(os) => u8::try_from(os).map_err(|_| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!("Invalid gzip header OS {}, expected 0-255", os),
+                )
+            })?,
+            None => gz_header::OS_CODE,
+        };
+
+        let extra = header.extra.map(|extra| extra.to_vec());
+        if let Some(extra) = extra
+            .as_ref()
+            .filter(|extra| extra.len() > u16::MAX as usize)
+        {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Gzip header extra field is {} bytes, expected at most {}",
+                    extra.len(),
+                    u16::MAX
+                ),
+            ));
+        }
+
+        Ok(Self {
+            text: header.text.unwrap_or(false),
+            mtime: header.mtime.unwrap_or(0),
+            os,
+            extra,
+            name: to_c_string("name", header.name)?,
+            comment: to_c_string("comment", header.comment)?,
+            header_crc: header.header_crc.unwrap_or(false),
+        })
+    }
+}
+
+/// A gzip header handed to deflate, which keeps a pointer to `raw` (and through it, to the fields of `data`) until
+/// the header has been written. Boxed so that pointer stays valid when the compressor moves.
+struct AttachedGzipHeader {
+    data: GzipHeaderData,
+    raw: gz_header,
+}
+
+/// Hands `data` to the deflate stream behind `stream_ptr` as the gzip header to write.
+///
+/// The returned box must outlive the stream, or at least stay alive until the header has been written.
+fn attach_gzip_header(
+    stream_ptr: NonNull<z_stream>,
+    data: GzipHeaderData,
+) -> Result<Box<AttachedGzipHeader>> {
+    let mut header = Box::new(AttachedGzipHeader {
+        data,
+        raw: gz_header::default(),
+    });
+
+    let AttachedGzipHeader { data, raw } = &mut *header;
+    raw.text = data.text.into();
+    raw.time = data.mtime.into();
+    raw.os = data.os.into();
+    raw.hcrc = data.header_crc.into();
+    if let Some(extra) = &mut data.extra {
+        raw.extra = extra.as_mut_ptr();
+        raw.extra_len = extra.len() as u32;
+    }
+    if let Some(name) = &data.name {
+        raw.name = name.as_ptr() as *mut u8;
+    }
+    if let Some(comment) = &data.comment {
+        raw.comment = comment.as_ptr() as *mut u8;
+    }
+
+    let raw_ptr: *mut gz_header = raw;
+
+    // SAFETY: stream_ptr is valid, and the caller keeps the header alive for as long as deflate may read it. extra is
+    // readable for extra_len bytes, and name and comment are NUL-terminated
+    let ret_code = match unsafe { DeflateStream::from_stream_mut(stream_ptr.as_ptr()) } {
+        Some(deflate_stream_ref) => unsafe {
+            deflate::set_header(deflate_stream_ref, Some(&mut *raw_ptr))
+        },
+        None => ReturnCode::StreamError,
+    };
+
+    if ret_code != ReturnCode::Ok {
+        return Err(Error::new(
+            Status::GenericFailure,
+            format!("Failed to set gzip header: {:?}", ret_code),
+        ));
+    }
+
+    Ok(header)
+}
+
+/// Flushes whatever deflate is holding on to as a block, then switches the stream over to `level` and `strategy`. The
+/// flushed output is appended to `output_buffer`.
+fn switch_params(
+    stream: &mut z_stream,
+    level: i32,
+    strategy: Strategy,
+    temp_out_buf: &mut [u8],
+    output_buffer: &mut Vec<u8>,
+) -> Result<Option<ReturnCode>> {
+    // Flushing up front leaves deflate::params with nothing to flush itself, so it can't run out of output space
+    let run = deflate_input(
+        stream,
+        &[],
+        DeflateFlush::Block,
+        temp_out_buf,
+        output_buffer,
+    )?;
+    if run.failure.is_some() {
+        return Ok(run.failure);
+    }
+
+    stream.next_out = temp_out_buf.as_mut_ptr();
+    stream.avail_out = 0;
+
+    // SAFETY: Our pointers are all valid
+    let ret_code = match unsafe { DeflateStream::from_stream_mut(stream) } {
+        Some(deflate_stream_ref) => deflate::params(deflate_stream_ref, level, strategy),
+        None => ReturnCode::StreamError,
+    };
+
+    Ok((ret_code != ReturnCode::Ok).then_some(ret_code))
+}
+
+pub(crate) struct DeflateRun {
+    /// How much of the input deflate consumed
+    pub(crate) bytes_consumed: usize,
+    /// Whether deflate finished the stream
+    pub(crate) stream_end: bool,
+    /// Set if deflate stopped because of an error
+    pub(crate) failure: Option<ReturnCode>,
+}
+
+/// Feeds `input` through deflate with the given flush mode, appending the output to `output_buffer` one
+/// `temp_out_buf` sized chunk at a time. Keeps going for as long as deflate fills the whole output chunk.
+pub(crate) fn deflate_input(
+    stream: &mut z_stream,
+    input: &[u8],
+    flush: DeflateFlush,
+    temp_out_buf: &mut [u8],
+    output_buffer: &mut Vec<u8>,
+) -> Result<DeflateRun> {
+    let mut run = DeflateRun {
+        bytes_consumed: 0,
+        stream_end: false,
+        failure: None,
+    };
+
+    stream.next_in = input.as_ptr() as *mut u8;
+    stream.avail_in = input
+        .len()
+        .try_into()
+        .map_err(|_| Error::new(Status::GenericFailure, "Input chunk too large"))?;
+
+    loop {
+        stream.next_out = temp_out_buf.as_mut_ptr();
+        stream.avail_out = temp_out_buf
+            .len()
+            .try_into()
+            .map_err(|_| Error::new(Status::GenericFailure, "Output chunk size too large"))?;
+
+        let avail_in_before_deflate = stream.avail_in;
+
+        // SAFETY: Our pointers are all valid
+        let result_code = match unsafe { DeflateStream::from_stream_mut(stream) } {
+            Some(deflate_stream_ref) => deflate::deflate(deflate_stream_ref, flush),
+            None => {
+                run.failure = Some(ReturnCode::StreamError);
+                return Ok(run);
+            }
+        };
+
+        let written_in_call = temp_out_buf.len() - stream.avail_out as usize;
+        output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+        run.bytes_consumed += (avail_in_before_deflate - stream.avail_in) as usize;
+
+        match result_code {
+            ReturnCode::StreamEnd => {
+                run.stream_end = true;
+                return Ok(run);
+            }
+            // BufError only means no progress was possible, which is fine as long as there's nothing left to do
+            ReturnCode::Ok | ReturnCode::BufError => {
+                // The output chunk was filled, so deflate may have more to give us
+                if stream.avail_out == 0 {
+                    continue;
+                }
+
+                return Ok(run);
+            }
+            other_code => {
+                run.failure = Some(other_code);
+                return Ok(run);
+            }
+        }
+    }
+}
+
+/// How deflate goes about finding matches (zlib's `strategy` parameter).
+#[napi]
+pub enum CompressionStrategy {
+    /// Regular LZ77 matching followed by Huffman coding
+    Default,
+    /// Favors Huffman coding over short matches, for data made of small, somewhat random values
+    Filtered,
+    /// Huffman coding only, without any matching
+    HuffmanOnly,
+    /// Only matches runs of the same byte (distance 1), as for image data such as PNG
+    Rle,
+    /// Uses the fixed Huffman codes instead of building dynamic ones, for small inputs
+    Fixed,
+}
+
+impl From<CompressionStrategy> for Strategy {
+    fn from(strategy: CompressionStrategy) -> Self {
+        match strategy {
+            CompressionStrategy::Default => Strategy::Default,
+            CompressionStrategy::Filtered => Strategy::Filtered,
+            CompressionStrategy::HuffmanOnly => Strategy::HuffmanOnly,
+            CompressionStrategy::Rle => Strategy::Rle,
+            CompressionStrategy::Fixed => Strategy::Fixed,
+        }
+    }
+}
+
+#[napi]
+pub enum FlushMode {
+    /// Flush all pending output, without aligning it to a byte boundary
+    Partial,
+    /// Flush all pending output and align it to a byte boundary (the usual `Z_SYNC_FLUSH`)
+    Sync,
+    /// Like `Sync`, but also resets the compression state so decompression can restart from this point
+    Full,
+    /// Complete the stream, writing its trailer
+    Finish,
+}
+
+impl From<FlushMode> for DeflateFlush {
+    fn from(mode: FlushMode) -> Self {
+        match mode {
+            FlushMode::Partial => DeflateFlush::PartialFlush,
+            FlushMode::Sync => DeflateFlush::SyncFlush,
+            FlushMode::Full => DeflateFlush::FullFlush,
+            FlushMode::Finish => DeflateFlush::Finish,
+        }
+    }
+}
+
+#[napi]
+pub struct DeflateCompressor {
+    chunk_size: u32,
+    // The level and strategy deflate was initialized with, restored after writeStoredBlock switches to level 0
+    level: i32,
+    strategy: Strategy,
+    // The window bits deflate was initialized with. zlib-rs keeps the stream's state private, so it can't be read back
+    window_bits: i32,
+    // Pointer to the heap-allocated z_stream
+    stream_ptr: NonNull<z_stream>,
+    // Set once the stream has been finished or hit a terminal error
+    finished: bool,
+    // Set once dispose has torn the stream down
+    disposed: bool,
+    // The gzip header deflate was handed, kept alive for as long as the stream may still read it
+    gzip_header: Option<Box<AttachedGzipHeader>>,
+}
+
+impl Drop for DeflateCompressor {
+    fn drop(&mut self) {
+        // SAFETY: NonNull guarantees that the stream_ptr is valid. Additionally, since this is the Drop trait,
+        // we should have no problems with double-frees or dangling pointers.
+        unsafe {
+            if let Some(deflate_stream_ref) =
+                DeflateStream::from_stream_mut(self.stream_ptr.as_ptr())
+            {
+                let _ = deflate::end(deflate_stream_ref);
+            }
+
+            let _ = Box::from_raw(self.stream_ptr.as_ptr());
+        }
+    }
+}
+
+impl DeflateCompressor {
+    /// Creates a compressor from an options object. This is what the constructor ends up calling.
+    pub fn with_options(options: CompressorOptions) -> Result<Self> {
+        let chunk_size = options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        if chunk_size == 0 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "Chunk size must be greater than 0",
+            ));
+        }
+
+        let level = resolve_level(options.level)?;
+        let mut config = DeflateConfig::new(level);
+        if let Some(window_bits) = options.window_bits {
+            config.window_bits = validate_deflate_window_bits(window_bits)?;
+        }
+        if let Some(strategy) = options.strategy {
+            config.strategy = strategy.into();
+        }
+
+        let mut stream = Box::new(z_stream::default());
+
+        // Initialize the stream for deflation
+        let ret_code = deflate::init(&mut stream, config);
+        if ret_code != ReturnCode::Ok {
+            return Err(Error::new(
+                Status::GenericFailure,
+                format!("Failed to initialize deflate stream: {:?}", ret_code),
+            ));
+        }
+
+        let stream_ptr = NonNull::new(Box::into_raw(stream)).ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                "Failed to get stream pointer after init",
+            )
+        })?;
+
+        Ok(Self {
+            chunk_size,
+            level,
+            strategy: config.strategy,
+            window_bits: effective_window_bits(config.window_bits),
+            stream_ptr,
+            finished: false,
+            disposed: false,
+            gzip_header: None,
+        })
+    }
+
+    /// Feeds the bytes of `iter` into the compressor without flushing, writing whatever deflate produces to `writer`.
+    ///
+    /// This is the Rust counterpart of `push`, for using the crate as a library. Bytes are gathered into chunks of the
+    /// compressor's chunk size, so the input never has to be in memory all at once. Returns how many bytes were fed
+    /// in. Like `push`, the stream is left open, so `finish` still has to be called to flush it.
+    pub fn push_iter<I, W>(&mut self, iter: I, writer: &mut W) -> io::Result<usize>
+    where
+        I: IntoIterator<Item = u8>,
+        W: Write,
+    {
+        if self.disposed {
+            return Err(io::Error::other(COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Err(io::Error::other("Compressor has already finished"));
+        }
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let chunk_size = self.chunk_size as usize;
+        let mut iter = iter.into_iter();
+        let mut input_chunk = Vec::with_capacity(chunk_size);
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; chunk_size];
+        let mut bytes_consumed = 0;
+
+        loop {
+            input_chunk.clear();
+            input_chunk.extend(iter.by_ref().take(chunk_size));
+            if input_chunk.is_empty() {
+                return Ok(bytes_consumed);
+            }
+
+            output_buffer.clear();
+            let run = deflate_input(
+                stream,
+                &input_chunk,
+                DeflateFlush::NoFlush,
+                &mut temp_out_buf,
+                &mut output_buffer,
+            )
+            .map_err(|err| io::Error::other(err.reason))?;
+
+            if let Some(ret_code) = run.failure {
+                self.finished = true;
+                return Err(io::Error::other(format!("Deflate error: {:?}", ret_code)));
+            }
+
+            writer.write_all(&output_buffer)?;
+            bytes_consumed += run.bytes_consumed;
+        }
+    }
+}
+
+#[napi]
+impl DeflateCompressor {
+    /// Creates a deflate compressor from a chunk size and optional level, or from a `CompressorOptions` object.
+    /// `level` defaults to 6 and the chunk size to 16 KiB. Streams are zlib-wrapped unless `windowBits` says otherwise.
+    #[napi(constructor)]
+    pub fn new(
+        options: Option<Either<u32, CompressorOptions>>,
+        level: Option<u32>,
+    ) -> Result<Self> {
+        Self::with_options(CompressorOptions::from_args(options, level))
+    }
+
+    /// The window bits the deflate stream uses, which determine its window size and whether it is zlib-wrapped (9-15),
+    /// raw (-15 to -9) or gzip-wrapped (25-31). Streams created with 8 window bits report 9, since deflate raises
+    /// them to that.
+    #[napi(getter)]
+    pub fn window_bits(&self) -> i32 {
+        self.window_bits
+    }
+
+    /// Sets the metadata written into the gzip header (zlib's `deflateSetHeader`), such as the file name, comment,
+    /// modification time and extra field.
+    ///
+    /// Only gzip-wrapped streams (window bits 25-31) have a header, and it has to be set before any output has been
+    /// produced. Otherwise this throws. Setting it again before then replaces the previous header.
+    #[napi]
+    pub fn set_header(&mut self, header: GzipHeader) -> Result<()> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        if !(25..=31).contains(&self.window_bits) {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Gzip headers can only be set on gzip-wrapped streams",
+            ));
+        }
+
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        let stream = unsafe { self.stream_ptr.as_ref() };
+        if self.finished || stream.total_out != 0 {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "The gzip header can only be set before any output has been produced",
+            ));
+        }
+
+        let header = attach_gzip_header(self.stream_ptr, header.try_into()?)?;
+        self.gzip_header = Some(header);
+
+        Ok(())
+    }
+
+    /// Upper bound on the size of the stream this compressor produces from `inputLen` bytes compressed in one go (zlib's
+    /// `deflateBound`), for sizing output buffers up front.
+    ///
+    /// Unlike `compressBoundGzip`, this goes by the stream's own settings: its wrapper and the header set through
+    /// `setHeader`, and its window bits and level, which allow a tighter bound for some streams.
+    #[napi]
+    pub fn compress_bound(&self, input_len: u32) -> Result<u32> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        // SAFETY: NonNull guarantees that the stream_ptr is valid, and deflate::bound only reads from the stream
+        let stream = unsafe { DeflateStream::from_stream_mut(self.stream_ptr.as_ptr()) };
+        let bound = deflate::bound(stream, input_len as usize);
+
+        u32::try_from(bound).map_err(|_| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Input length {} is too large to bound", input_len),
+            )
+        })
+    }
+
+    /// Feeds `data` into the compressor without flushing.
+    ///
+    /// deflate is free to hold on to input to improve compression, so `data` may well be `null` until the stream is
+    /// flushed or finished.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Ok(PushResult::error("Compressor has already finished"));
+        }
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let run = deflate_input(
+            stream,
+            &data,
+            DeflateFlush::NoFlush,
+            &mut temp_out_buf,
+            &mut output_buffer,
+        )?;
+
+        if let Some(ret_code) = run.failure {
+            self.finished = true;
+            return Ok(PushResult::error(format!("Deflate error: {:?}", ret_code)));
+        }
+
+        Ok(PushResult::ok(run.bytes_consumed, output_buffer))
+    }
+
+    /// Flushes everything deflate is holding on to.
+    ///
+    /// With `FlushMode.Finish` this keeps calling deflate until the stream ends, so the output is never truncated, and
+    /// the compressor can't be used afterwards. The other modes leave the stream open for more data.
+    #[napi]
+    pub fn finish(&mut self, flush: FlushMode) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Ok(PushResult::error("Compressor has already finished"));
+        }
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let run = deflate_input(
+            stream,
+            &[],
+            flush.into(),
+            &mut temp_out_buf,
+            &mut output_buffer,
+        )?;
+
+        if let Some(ret_code) = run.failure {
+            self.finished = true;
+            return Ok(PushResult::error(format!("Deflate error: {:?}", ret_code)));
+        }
+
+        if matches!(flush, FlushMode::Finish) {
+            self.finished = true;
+
+            if !run.stream_end {
+                return Ok(PushResult::error(
+                    "Deflate did not reach the end of the stream",
+                ));
+            }
+        }
+
+        Ok(PushResult::ok(run.bytes_consumed, output_buffer))
+    }
+
+    /// Feeds every buffer in `chunks` into the compressor in order, then flushes with `flush`, returning all of the
+    /// output at once.
+    ///
+    /// Equivalent to calling `push` for each chunk followed by `finish(flush)`, but in a single call. Failures are
+    /// thrown, and leave the compressor finished just like with `push`.
+    #[napi]
+    pub fn compress_many(&mut self, chunks: Vec<Buffer>, flush: FlushMode) -> Result<Buffer> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Compressor has already finished",
+            ));
+        }
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let inputs = chunks
+            .iter()
+            .map(|chunk| (chunk.as_ref(), DeflateFlush::NoFlush))
+            .chain([(&[][..], flush.into())]);
+
+        for (input, flush) in inputs {
+            let run = deflate_input(stream, input, flush, &mut temp_out_buf, &mut output_buffer)?;
+
+            if let Some(ret_code) = run.failure {
+                self.finished = true;
+                return Err(Error::new(
+                    Status::GenericFailure,
+                    format!("Deflate error: {:?}", ret_code),
+                ));
+            }
+
+            if flush == DeflateFlush::Finish {
+                self.finished = true;
+
+                if !run.stream_end {
+                    return Err(Error::new(
+                        Status::GenericFailure,
+                        "Deflate did not reach the end of the stream",
+                    ));
+                }
+            }
+        }
+
+        Ok(output_buffer.into())
+    }
+
+    /// Writes `data` into the stream as stored (uncompressed) blocks, without attempting to compress it.
+    ///
+    /// Meant for mixed-content streams, where already-compressed or encrypted segments would only grow if deflated.
+    /// Anything deflate was holding on to is flushed first, and the stream goes back to its own level afterwards.
+    /// Returns the output produced along the way.
+    #[napi]
+    pub fn write_stored_block(&mut self, data: Buffer) -> Result<Buffer> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Compressor has already finished",
+            ));
+        }
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        // Level 0 is what makes deflate emit stored blocks
+        let mut failure = switch_params(
+            stream,
+            0,
+            Strategy::Default,
+            &mut temp_out_buf,
+            &mut output_buffer,
+        )?;
+
+        if failure.is_none() {
+            failure = deflate_input(
+                stream,
+                &data,
+                DeflateFlush::Block,
+                &mut temp_out_buf,
+                &mut output_buffer,
+            )?
+            .failure;
+        }
+
+        if failure.is_none() {
+            failure = switch_params(
+                stream,
+                self.level,
+                self.strategy,
+                &mut temp_out_buf,
+                &mut output_buffer,
+            )?;
+        }
+
+        if let Some(ret_code) = failure {
+            self.finished = true;
+            return Err(Error::new(
+                Status::GenericFailure,
+                format!("Deflate error: {:?}", ret_code),
+            ));
+        }
+
+        Ok(output_buffer.into())
+    }
+
+    /// Builds the 8-byte gzip trailer: the CRC-32 of the uncompressed data followed by its size modulo 2^32
+    /// (ISIZE), both little-endian.
+    ///
+    /// Meant for callers assembling gzip members by hand around raw deflate data. Both values must fit in 32 bits.
+    #[napi]
+    pub fn write_gzip_footer(crc32: i64, isize: i64) -> Result<Buffer> {
+        let to_u32 = |name: &str, value: i64| {
+            u32::try_from(value).map_err(|_| {
+                Error::new(
+                    Status::InvalidArg,
+                    format!(
+                        "Invalid {} {}, expected a 32-bit unsigned integer",
+                        name, value
+                    ),
+                )
+            })
+        };
+
+        let mut footer = Vec::with_capacity(8);
+        footer.extend_from_slice(&to_u32("CRC-32", crc32)?.to_le_bytes());
+        footer.extend_from_slice(&to_u32("ISIZE", isize)?.to_le_bytes());
+
+        Ok(footer.into())
+    }
+
+    /// Tears the deflate stream down right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Compressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        if self.disposed {
+            return;
+        }
+
+        self.disposed = true;
+        self.finished = true;
+
+        // SAFETY: stream_ptr is valid. deflate::end leaves the stream's state null, which makes Drop skip tearing it
+        // down a second time
+        if let Some(deflate_stream_ref) =
+            unsafe { DeflateStream::from_stream_mut(self.stream_ptr.as_ptr()) }
+        {
+            let _ = deflate::end(deflate_stream_ref);
+        }
+    }
+}
+
+/// Duplicates the state of `source` (zlib's `deflateCopy`), so compression can carry on from the same point in two
+/// different ways, for example to find out which flush point compresses better.
+///
+/// The copy has the same chunk size and finished state, and from then on is completely independent of `source`:
+/// pushing the same data into both produces identical output.
+#[napi]
+pub fn deflate_copy(source: &DeflateCompressor) -> Result<DeflateCompressor> {
+    if source.disposed {
+        return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+    }
+
+    // SAFETY: NonNull guarantees that the stream_ptr is valid, and the source stream is only read from while copying.
+    let source_stream = unsafe { DeflateStream::from_stream_mut(source.stream_ptr.as_ptr()) }
+        .ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                "Failed to get deflate stream reference",
+            )
+        })?;
+
+    let mut copy = Box::new(MaybeUninit::<DeflateStream>::uninit());
+    let ret_code = deflate::copy(&mut copy, source_stream);
+    if ret_code != ReturnCode::Ok {
+        return Err(Error::new(
+            Status::GenericFailure,
+            format!("Failed to copy deflate stream: {:?}", ret_code),
+        ));
+    }
+
+    // deflate::copy fully initialized the copy, and DeflateStream has the same layout as z_stream, so it can be
+    // owned and torn down exactly like a stream from with_options
+    let stream_ptr = NonNull::new(Box::into_raw(copy).cast::<z_stream>()).ok_or_else(|| {
+        Error::new(
+            Status::GenericFailure,
+            "Failed to get stream pointer after copy",
+        )
+    })?;
+
+    // The copy still points at the source's gzip header, which it must not outlive, so it gets a header of its own
+    let gzip_header = source
+        .gzip_header
+        .as_ref()
+        .map(|header| attach_gzip_header(stream_ptr, header.data.clone()))
+        .transpose()?;
+
+    Ok(DeflateCompressor {
+        chunk_size: source.chunk_size,
+        level: source.level,
+        strategy: source.strategy,
+        window_bits: source.window_bits,
+        stream_ptr,
+        finished: source.finished,
+        disposed: false,
+        gzip_header,
+    })
+}
+
+/// Size of the zlib wrapper `deflate::bound` accounts for when it isn't given a stream.
+const ZLIB_WRAPPER_LEN: usize = 6;
+/// Size of the smallest gzip wrapper: the 10-byte header followed by the CRC-32 and length trailer.
+const GZIP_WRAPPER_LEN: usize = 18;
+
+/// Upper bound on the size of a gzip-wrapped stream compressed from `inputLen` bytes in one go.
+///
+/// This assumes the default header. Fields set through `setHeader` make it bigger: the extra field by its length plus
+/// 2, the name and comment by their length plus 1 and the header CRC by 2.
+#[napi]
+pub fn compress_bound_gzip(input_len: u32) -> Result<u32> {
+    let bound = deflate::bound(None, input_len as usize) - ZLIB_WRAPPER_LEN + GZIP_WRAPPER_LEN;
+
+    u32::try_from(bound).map_err(|_| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Input length {} is too large to bound", input_len),
+        )
+    })
+}
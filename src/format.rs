@@ -0,0 +1,258 @@
+use napi::bindgen_prelude::{Buffer, Result, Status};
+use napi::Error;
+use zlib_rs::{Inflate, InflateFlush};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const LZ4_FRAME_MAGIC: &[u8] = &[0x04, 0x22, 0x4d, 0x18];
+// Stream identifier chunk of the snappy framing format: chunk type, 3-byte length, "sNaPpY"
+const SNAPPY_STREAM_IDENTIFIER: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+
+// FDICT, set when a preset dictionary id follows the zlib header
+const ZLIB_FLG_DICTIONARY: u8 = 0x20;
+
+// Gzip header flags (RFC 1952)
+const GZIP_FLG_HEADER_CRC: u8 = 0x02;
+const GZIP_FLG_EXTRA: u8 = 0x04;
+const GZIP_FLG_NAME: u8 = 0x08;
+const GZIP_FLG_COMMENT: u8 = 0x10;
+const GZIP_FLG_RESERVED: u8 = 0xe0;
+
+const MAGIC_CONFIDENCE: f32 = 1.0;
+const PROBABLE_CONFIDENCE: f32 = 0.5;
+
+#[napi]
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    Zlib,
+    Deflate,
+    Zstd,
+    Brotli,
+    Lz4,
+    Snappy,
+}
+
+#[napi(object, object_from_js = false)]
+pub struct ProbeResult {
+    /// The detected format, or absent if nothing matched
+    pub format: Option<CompressionFormat>,
+    /// 1.0 for magic-byte matches, 0.5 for probable matches and 0.0 when nothing matched
+    pub confidence: f32,
+    /// The window bits to hand to zlib for this stream, if it is a zlib-family format
+    pub window_bits: Option<i32>,
+}
+
+impl ProbeResult {
+    fn matched(format: CompressionFormat, confidence: f32, window_bits: Option<i32>) -> Self {
+        Self {
+            format: Some(format),
+            confidence,
+            window_bits,
+        }
+    }
+}
+
+/// Reads the two-byte zlib header, returning the window bits it declares if the header is valid.
+pub(crate) fn zlib_header_window_bits(data: &[u8]) -> Option<i32> {
+    let [cmf, flg, ..] = *data else {
+        return None;
+    };
+
+    let compression_method = cmf & 0x0f;
+    let compression_info = cmf >> 4;
+    if compression_method != 8 || compression_info > 7 {
+        return None;
+    }
+
+    if (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return None;
+    }
+
+    Some(i32::from(compression_info) + 8)
+}
+
+/// Walks the gzip member header at the start of `data`, returning its length if it is complete and valid.
+pub(crate) fn gzip_header_len(data: &[u8]) -> Option<usize> {
+    // ID1, ID2, CM, FLG, MTIME (4 bytes), XFL and OS
+    let [_, _, compression_method, flags, ..] = *data.get(..10)? else {
+        return None;
+    };
+
+    if !data.starts_with(GZIP_MAGIC) || compression_method != 8 || flags & GZIP_FLG_RESERVED != 0 {
+        return None;
+    }
+
+    let mut offset = 10;
+
+    if flags & GZIP_FLG_EXTRA != 0 {
+        let extra_len = u16::from_le_bytes([*data.get(offset)?, *data.get(offset + 1)?]);
+        offset += 2 + usize::from(extra_len);
+    }
+
+    // The file name and comment are zero-terminated
+    for flag in [GZIP_FLG_NAME, GZIP_FLG_COMMENT] {
+        if flags & flag != 0 {
+            offset += data.get(offset..)?.iter().position(|&byte| byte == 0)? + 1;
+        }
+    }
+
+    if flags & GZIP_FLG_HEADER_CRC != 0 {
+        let header_crc = u16::from_le_bytes([*data.get(offset)?, *data.get(offset + 1)?]);
+        if header_crc != zlib_rs::crc32(0, &data[..offset]) as u16 {
+            return None;
+        }
+
+        offset += 2;
+    }
+
+    (offset <= data.len()).then_some(offset)
+}
+
+/// Checks whether the first deflate block header in `data` could plausibly start a raw deflate stream.
+fn is_probable_raw_deflate(data: &[u8]) -> bool {
+    let Some(&first) = data.first() else {
+        return false;
+    };
+
+    match (first >> 1) & 0b11 {
+        // Stored block: the header is padded to a byte boundary and followed by LEN and its one's complement
+        0b00 => match data {
+            [_, len_lo, len_hi, nlen_lo, nlen_hi, ..] => {
+                u16::from_le_bytes([*len_lo, *len_hi]) == !u16::from_le_bytes([*nlen_lo, *nlen_hi])
+            }
+            _ => false,
+        },
+        // Fixed or dynamic Huffman block
+        0b01 | 0b10 => true,
+        // Reserved block type, always invalid
+        _ => false,
+    }
+}
+
+pub(crate) fn probe(data: &[u8]) -> ProbeResult {
+    if data.starts_with(GZIP_MAGIC) {
+        return ProbeResult::matched(CompressionFormat::Gzip, MAGIC_CONFIDENCE, Some(31));
+    }
+
+    if data.starts_with(ZSTD_MAGIC) {
+        return ProbeResult::matched(CompressionFormat::Zstd, MAGIC_CONFIDENCE, None);
+    }
+
+    if data.starts_with(LZ4_FRAME_MAGIC) {
+        return ProbeResult::matched(CompressionFormat::Lz4, MAGIC_CONFIDENCE, None);
+    }
+
+    if data.starts_with(SNAPPY_STREAM_IDENTIFIER) {
+        return ProbeResult::matched(CompressionFormat::Snappy, MAGIC_CONFIDENCE, None);
+    }
+
+    if let Some(window_bits) = zlib_header_window_bits(data) {
+        return ProbeResult::matched(
+            CompressionFormat::Zlib,
+            PROBABLE_CONFIDENCE,
+            Some(window_bits),
+        );
+    }
+
+    if is_probable_raw_deflate(data) {
+        return ProbeResult::matched(CompressionFormat::Deflate, PROBABLE_CONFIDENCE, Some(-15));
+    }
+
+    ProbeResult {
+        format: None,
+        confidence: 0.0,
+        window_bits: None,
+    }
+}
+
+/// Guesses the compression format of `data` by looking at its first bytes.
+///
+/// Brotli streams carry no magic bytes and cannot be told apart from arbitrary data, so they are never reported.
+#[napi]
+pub fn probe_format(data: Buffer) -> ProbeResult {
+    probe(&data)
+}
+
+/// Inflates the deflate data following a zlib header through a fixed scratch buffer, checking the Adler-32 trailer
+/// after it against the checksum of the output. Returns `None` if the deflate data or the trailer is cut short.
+fn zlib_trailer_matches(deflate_data: &[u8], window_bits: i32) -> Option<bool> {
+    let mut inflate = Inflate::new(false, window_bits as u8);
+    let mut scratch = vec![0u8; 32 * 1024];
+    let mut checksum = zlib_rs::adler32(1, &[]);
+    let mut input = deflate_data;
+
+    loop {
+        let total_in_before = inflate.total_in();
+        let total_out_before = inflate.total_out();
+
+        let status = inflate.decompress(input, &mut scratch, InflateFlush::NoFlush);
+
+        let consumed = (inflate.total_in() - total_in_before) as usize;
+        let produced = (inflate.total_out() - total_out_before) as usize;
+        checksum = zlib_rs::adler32(checksum, &scratch[..produced]);
+        input = &input[consumed..];
+
+        match status {
+            Ok(zlib_rs::Status::StreamEnd) => break,
+            // Out of input with the stream still going
+            Ok(_) if consumed == 0 && produced == 0 => return None,
+            Ok(_) => {}
+            Err(_) => return Some(false),
+        }
+    }
+
+    let trailer = input.get(..4)?;
+    Some(u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) == checksum)
+}
+
+/// Checks whether `data` is, or starts like, a valid zlib stream.
+///
+/// The two-byte header must pass its check bits and declare deflate with a window of at most 32 KiB. If the first
+/// deflate block header is there too, it must not use the reserved block type. If the whole stream is there, it is
+/// inflated into a scratch buffer (without keeping the output) and the Adler-32 trailer must match the checksum of
+/// what it inflates to. That check is skipped for streams using a preset dictionary, which can't be inflated without
+/// it, and for streams that are cut short.
+#[napi]
+pub fn is_valid_zlib(data: Buffer) -> bool {
+    let Some(window_bits) = zlib_header_window_bits(&data) else {
+        return false;
+    };
+
+    // The header is followed by a 4-byte dictionary id when FDICT is set
+    let has_dictionary = data[1] & ZLIB_FLG_DICTIONARY != 0;
+    let header_len = if has_dictionary { 6 } else { 2 };
+
+    match data.get(header_len) {
+        Some(&first) if (first >> 1) & 0b11 == 0b11 => false,
+        Some(_) if !has_dictionary => {
+            zlib_trailer_matches(&data[header_len..], window_bits) != Some(false)
+        }
+        _ => true,
+    }
+}
+
+/// Checks whether `data` starts with a complete and valid gzip member header, without decompressing it.
+///
+/// Besides the magic bytes, the compression method must be deflate and no reserved flags may be set. The optional
+/// extra field, file name and comment must all be there, and the header CRC must match if there is one.
+#[napi]
+pub fn is_valid_gzip(data: Buffer) -> bool {
+    gzip_header_len(&data).is_some()
+}
+
+/// Combines the Adler-32 checksums of two consecutive pieces of data into the checksum of both (zlib's
+/// `adler32_combine`), where `len2` is the length of the second piece.
+///
+/// This lets large inputs be checksummed in parallel, one piece at a time, without going over all of the data again.
+#[napi]
+pub fn adler32_combine(adler1: u32, adler2: u32, len2: i64) -> Result<u32> {
+    let len2 = u64::try_from(len2).map_err(|_| {
+        Error::new(
+            Status::InvalidArg,
+            format!("Invalid length {}, expected a non-negative integer", len2),
+        )
+    })?;
+
+    Ok(zlib_rs::adler32_combine(adler1, adler2, len2))
+}
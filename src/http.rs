@@ -0,0 +1,377 @@
+#[cfg(feature = "brotli")]
+use crate::brotli::BrotliDecompressor;
+use crate::inflater::{inflate_input, with_scratch_buffer};
+use crate::options::DEFAULT_CHUNK_SIZE;
+use crate::result::{FinishResult, PushResult, DECOMPRESSOR_DISPOSED};
+#[cfg(feature = "zstd")]
+use crate::zstd::ZstdDecompressor;
+use napi::bindgen_prelude::{Buffer, Either, Null, Result, Status, Uint8Array};
+use napi::Error;
+use std::fs::File;
+use std::io::Write;
+use zlib_rs::{
+    c_api::z_stream,
+    inflate::{self, InflateConfig, InflateStream},
+    ReturnCode,
+};
+
+/// A zlib or gzip body, inflated without waiting for sync-flush suffixes.
+struct InflateStage {
+    // Boxed so the stream stays put, since inflate keeps a pointer back to it
+    stream: Box<z_stream>,
+    // The content coding this stage decodes, for error messages
+    encoding: &'static str,
+    stream_end: bool,
+}
+
+impl InflateStage {
+    fn new(window_bits: i32, encoding: &'static str) -> Result<Self> {
+        let mut stream = Box::new(z_stream::default());
+
+        let ret_code = inflate::init(&mut stream, InflateConfig { window_bits });
+        if ret_code != ReturnCode::Ok {
+            return Err(Error::new(
+                Status::GenericFailure,
+                format!("Failed to initialize inflate stream: {:?}", ret_code),
+            ));
+        }
+
+        Ok(Self {
+            stream,
+            encoding,
+            stream_end: false,
+        })
+    }
+
+    fn push(&mut self, input: &[u8]) -> Result<PushResult> {
+        // Anything after the end of the body is ignored
+        if self.stream_end {
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        let mut output_buffer = Vec::new();
+
+        let run = with_scratch_buffer(DEFAULT_CHUNK_SIZE as usize, |temp_out_buf| {
+            inflate_input(
+                &mut self.stream,
+                input,
+                temp_out_buf,
+                &mut output_buffer,
+                None,
+            )
+        })?;
+
+        if let Some(failure) = run.failure {
+            return Ok(PushResult::error(failure.message()));
+        }
+
+        self.stream_end = run.stream_end;
+
+        Ok(PushResult::ok(run.bytes_consumed, output_buffer))
+    }
+
+    fn finish(&mut self) -> FinishResult {
+        if !self.stream_end {
+            return FinishResult::error(format!("Unexpected end of {} stream", self.encoding));
+        }
+
+        FinishResult::ok(Vec::new())
+    }
+}
+
+impl Drop for InflateStage {
+    fn drop(&mut self) {
+        // SAFETY: The stream was initialized in new, so it is safe to tear it down
+        if let Some(inflate_stream_ref) =
+            unsafe { InflateStream::from_stream_mut(&mut *self.stream) }
+        {
+            inflate::end(inflate_stream_ref);
+        }
+    }
+}
+
+/// One content coding to undo.
+enum Stage {
+    Inflate(InflateStage),
+    #[cfg(feature = "brotli")]
+    Brotli(BrotliDecompressor),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdDecompressor),
+}
+
+impl Stage {
+    /// Maps a content coding to the stage decoding it, or `None` for `identity`.
+    fn for_encoding(encoding: &str) -> Result<Option<Self>> {
+        let stage = match encoding.to_ascii_lowercase().as_str() {
+            "identity" => return Ok(None),
+            "gzip" | "x-gzip" => Stage::Inflate(InflateStage::new(31, "gzip")?),
+            // RFC 9110 defines deflate as zlib-wrapped, not raw deflate
+            "deflate" => Stage::Inflate(InflateStage::new(15, "deflate")?),
+            #[cfg(feature = "brotli")]
+            "br" => Stage::Brotli(BrotliDecompressor::new(DEFAULT_CHUNK_SIZE)?),
+            #[cfg(feature = "zstd")]
+            "zstd" => Stage::Zstd(ZstdDecompressor::new(DEFAULT_CHUNK_SIZE, None)?),
+            _ => {
+                return Err(Error::new(
+                    Status::InvalidArg,
+                    format!("Unsupported content encoding {}", encoding),
+                ))
+            }
+        };
+
+        Ok(Some(stage))
+    }
+
+    fn push(&mut self, input: Vec<u8>) -> Result<PushResult> {
+        match self {
+            Stage::Inflate(stage) => stage.push(&input),
+            #[cfg(feature = "brotli")]
+            Stage::Brotli(decompressor) => decompressor.push(Uint8Array::new(input)),
+            #[cfg(feature = "zstd")]
+            Stage::Zstd(decompressor) => decompressor.push(Uint8Array::new(input)),
+        }
+    }
+
+    fn finish(&mut self) -> Result<FinishResult> {
+        match self {
+            Stage::Inflate(stage) => Ok(stage.finish()),
+            #[cfg(feature = "brotli")]
+            Stage::Brotli(decompressor) => decompressor.finish(),
+            #[cfg(feature = "zstd")]
+            Stage::Zstd(decompressor) => decompressor.finish(),
+        }
+    }
+}
+
+fn into_bytes(data: Either<Buffer, Null>) -> Vec<u8> {
+    match data {
+        Either::A(buffer) => buffer.to_vec(),
+        Either::B(_) => Vec::new(),
+    }
+}
+
+#[napi]
+pub struct HttpDecompressor {
+    // In decoding order, which is the reverse of the order the codings are listed in
+    stages: Vec<Stage>,
+    // Set once the body has been finished or hit a terminal error
+    finished: bool,
+    // The terminal error the body hit, if any, so later calls don't report success over a broken body
+    error: Option<String>,
+    // Set once dispose has torn the stages down
+    disposed: bool,
+}
+
+fn write_error(path: &str, err: std::io::Error) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Failed to write {}: {}", path, err),
+    )
+}
+
+/// Writes `output` to the file at `path`, creating it the first time there is anything to write.
+fn write_output(file: &mut Option<File>, path: &str, output: &[u8]) -> Result<()> {
+    if output.is_empty() {
+        return Ok(());
+    }
+
+    let file = match file {
+        Some(file) => file,
+        None => file.insert(File::create(path).map_err(|err| write_error(path, err))?),
+    };
+
+    file.write_all(output).map_err(|err| write_error(path, err))
+}
+
+#[napi]
+impl HttpDecompressor {
+    /// Creates a decompressor undoing the codings listed in a `Content-Encoding` header, such as `gzip` or
+    /// `gzip, br`.
+    ///
+    /// Codings are undone in the reverse of the order they are listed in. `gzip`, `x-gzip`, `deflate` and `identity`
+    /// are always supported, while `br` and `zstd` need the matching features. Any other coding is rejected.
+    #[napi(factory)]
+    pub fn new_for_http_response(content_encoding: String) -> Result<Self> {
+        let mut stages = Vec::new();
+
+        for encoding in content_encoding.split(',').map(str::trim).rev() {
+            if encoding.is_empty() {
+                continue;
+            }
+
+            stages.extend(Stage::for_encoding(encoding)?);
+        }
+
+        Ok(Self {
+            stages,
+            finished: false,
+            error: None,
+            disposed: false,
+        })
+    }
+
+    /// Decompresses as much of `data` as possible, passing it through every coding in turn.
+    ///
+    /// `bytesConsumed` is how much of `data` the first coding consumed. Once a push has failed, every later one fails
+    /// with the same error.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        self.push_bytes(data.to_vec())
+    }
+
+    /// Checks that every coding reached the end of its stream, returning any output that was still pending.
+    ///
+    /// Fails with the error that broke the body if a push or an earlier finish failed.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        if self.disposed {
+            return Ok(FinishResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if let Some(error) = &self.error {
+            return Ok(FinishResult::error(error.clone()));
+        }
+
+        if self.finished {
+            return Ok(FinishResult::ok(Vec::new()));
+        }
+
+        self.finished = true;
+
+        let mut pending = Vec::new();
+
+        for stage in &mut self.stages {
+            if !pending.is_empty() {
+                let result = stage.push(std::mem::take(&mut pending))?;
+                if !result.ok {
+                    let error = result.error.unwrap_or_default();
+                    self.error = Some(error.clone());
+                    return Ok(FinishResult::error(error));
+                }
+
+                pending = into_bytes(result.data);
+            }
+
+            let result = stage.finish()?;
+            if !result.ok {
+                self.error = result.error.clone();
+                return Ok(result);
+            }
+
+            pending.extend(into_bytes(result.data));
+        }
+
+        Ok(FinishResult::ok(pending))
+    }
+
+    /// Decompresses the rest of the body, `data`, then finishes it like `finish`, writing the output to the file at
+    /// `path` instead of returning it.
+    ///
+    /// `data` goes through the codings in chunks, and each chunk of output is written as soon as it comes out, so
+    /// neither the body nor its output have to fit in memory at once, and none of it crosses over into JavaScript.
+    /// Failures to decompress the body, including ones from earlier pushes, or to write the file are thrown, as is
+    /// calling this on a body that was already finished or disposed of.
+    ///
+    /// The file is only created (or truncated) once there is output to write, or once an empty body has finished, so
+    /// a body that fails before producing anything leaves an existing file alone.
+    #[napi]
+    pub fn write_to_file(&mut self, path: String, data: Option<Uint8Array>) -> Result<()> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, DECOMPRESSOR_DISPOSED));
+        }
+
+        if let Some(error) = &self.error {
+            return Err(Error::new(Status::GenericFailure, error.clone()));
+        }
+
+        if self.finished {
+            return Err(Error::new(
+                Status::GenericFailure,
+                "Decompressor has already finished",
+            ));
+        }
+
+        let mut file = None;
+
+        for chunk in data
+            .as_deref()
+            .unwrap_or_default()
+            .chunks(DEFAULT_CHUNK_SIZE as usize)
+        {
+            let result = self.push_bytes(chunk.to_vec())?;
+            if !result.ok {
+                return Err(Error::new(
+                    Status::GenericFailure,
+                    result.error.unwrap_or_default(),
+                ));
+            }
+
+            write_output(&mut file, &path, &into_bytes(result.data))?;
+        }
+
+        let result = self.finish()?;
+        if !result.ok {
+            return Err(Error::new(
+                Status::GenericFailure,
+                result.error.unwrap_or_default(),
+            ));
+        }
+
+        write_output(&mut file, &path, &into_bytes(result.data))?;
+
+        let mut file = match file {
+            Some(file) => file,
+            None => File::create(&path).map_err(|err| write_error(&path, err))?,
+        };
+
+        file.flush().map_err(|err| write_error(&path, err))
+    }
+
+    /// Tears down the decoder of every coding right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.stages.clear();
+        self.finished = true;
+        self.disposed = true;
+    }
+}
+
+impl HttpDecompressor {
+    fn push_bytes(&mut self, data: Vec<u8>) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if let Some(error) = &self.error {
+            return Ok(PushResult::error(error.clone()));
+        }
+
+        if self.finished {
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        let mut bytes_consumed = data.len();
+        let mut chunk = data;
+
+        for (index, stage) in self.stages.iter_mut().enumerate() {
+            let result = stage.push(chunk)?;
+
+            if !result.ok {
+                self.finished = true;
+                self.error = result.error.clone();
+                return Ok(result);
+            }
+
+            if index == 0 {
+                bytes_consumed = result.bytes_consumed.unwrap_or_default() as usize;
+            }
+
+            chunk = into_bytes(result.data);
+        }
+
+        Ok(PushResult::ok(bytes_consumed, chunk))
+    }
+}
@@ -0,0 +1,1542 @@
+//! The inflate side of `ZlibDecompressor`, in plain Rust.
+//!
+//! Both front ends wrap [`Inflater`]: the Node-API class in `zlib` and, with the `wasm` feature, the wasm-bindgen one
+//! in `wasm`. Nothing in here depends on either of them, so the WebAssembly build doesn't pull Node-API in.
+
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use zlib_rs::{
+    c_api::z_stream,
+    inflate::{self, InflateConfig, InflateStream},
+    InflateFlush, ReturnCode,
+};
+
+/// Chunk size used when the options don't specify one.
+pub(crate) const DEFAULT_CHUNK_SIZE: u32 = 16 * 1024;
+
+/// Error every call into a decompressor fails with once it has been disposed.
+pub(crate) const DECOMPRESSOR_DISPOSED: &str = "Decompressor has been disposed";
+
+/// What kind of mistake an [`InflaterError`] is, which the front ends map onto their own error types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InflaterErrorKind {
+    /// An argument was out of range
+    InvalidArg,
+    /// Anything else
+    Failure,
+}
+
+/// Why a call failed outright, as opposed to a push handing back a failed result.
+#[derive(Debug)]
+pub struct InflaterError {
+    pub kind: InflaterErrorKind,
+    pub message: String,
+}
+
+impl InflaterError {
+    pub(crate) fn invalid_arg(message: impl Into<String>) -> Self {
+        Self {
+            kind: InflaterErrorKind::InvalidArg,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn failure(message: impl Into<String>) -> Self {
+        Self {
+            kind: InflaterErrorKind::Failure,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for InflaterError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for InflaterError {}
+
+pub type Result<T> = std::result::Result<T, InflaterError>;
+
+/// What a push produced, which each front end turns into its own result object.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushOutcome {
+    /// `bytes_consumed` is how many buffered bytes inflate consumed, 0 while a message is still being buffered
+    Ok {
+        bytes_consumed: usize,
+        output: Vec<u8>,
+    },
+    /// The push failed with this message
+    Failed(String),
+}
+
+impl PushOutcome {
+    fn ok(bytes_consumed: usize, output: Vec<u8>) -> Self {
+        Self::Ok {
+            bytes_consumed,
+            output,
+        }
+    }
+}
+
+/// Snapshot of the stream's counters.
+pub struct InflaterStats {
+    pub total_in: u64,
+    pub total_out: u64,
+    pub adler: u32,
+    /// `total_out / total_in`, or 0 if nothing has been read yet
+    pub ratio: f64,
+}
+
+/// How an [`Inflater::inflate_sync`] call went.
+pub struct SyncOutcome {
+    /// Whether a flush point was found
+    pub found: bool,
+    /// How many bytes of corrupted input were skipped to get there
+    pub bytes_skipped: u32,
+}
+
+/// How to set up an [`Inflater`]. Everything left out gets the same default as `ZlibDecompressor`'s.
+#[derive(Default)]
+pub struct InflaterOptions {
+    /// Size of the chunks the output is produced in, defaults to 16 KiB
+    pub chunk_size: Option<u32>,
+    /// The window bits inflate is initialized with, defaults to 15 (zlib-wrapped, 32 KiB window)
+    pub window_bits: Option<i32>,
+    /// The preset dictionary the stream was compressed with, if any
+    pub dictionary: Option<Vec<u8>>,
+    /// How many bytes a single call may produce
+    pub max_output_size: Option<u32>,
+}
+
+const Z_SYNC_FLUSH_SUFFIX: &[u8] = &[0, 0, 255, 255];
+
+/// Window bits that make inflate detect whether the stream is zlib or gzip-wrapped, with up to a 32 KiB window.
+const AUTO_DETECT_WINDOW_BITS: i32 = 32 + 15;
+
+// z_stream.data_type once inflate stopped between two deflate blocks with no bits left over, like it does right after
+// a sync flush
+const AT_BLOCK_BOUNDARY: i32 = 128;
+
+/// Checks that `window_bits` is one of the values zlib accepts for inflation:
+/// 8..=15 for zlib, -15..=-8 for raw deflate, 24..=31 for gzip and 40..=47 for zlib/gzip auto-detection.
+/// 0, 16 and 32 are also accepted for zlib, gzip and auto-detection respectively, in which case the window size is read
+/// from the zlib header.
+pub(crate) fn validate_window_bits(window_bits: i32) -> Result<i32> {
+    match window_bits {
+        0 | 8..=15 | -15..=-8 | 16 | 24..=31 | 32 | 40..=47 => Ok(window_bits),
+        _ => Err(InflaterError::invalid_arg(format!(
+            "Invalid window bits {}",
+            window_bits
+        ))),
+    }
+}
+
+/// Inflate needs room for at least one byte of output per call, or it never makes progress.
+fn validate_chunk_size(chunk_size: u32) -> Result<u32> {
+    if chunk_size == 0 {
+        return Err(InflaterError::invalid_arg(
+            "Chunk size must be greater than 0",
+        ));
+    }
+
+    Ok(chunk_size)
+}
+
+/// Why a run of inflate stopped before consuming all of its input.
+pub(crate) enum InflateFailure {
+    /// The z_stream could not be viewed as an initialized inflate stream
+    InvalidStream,
+    /// inflate returned a terminal error code
+    Code(ReturnCode),
+    /// The output grew past the configured limit
+    OutputLimit,
+}
+
+impl InflateFailure {
+    pub(crate) fn message(&self) -> String {
+        match self {
+            InflateFailure::InvalidStream => "Failed to get inflate stream reference".to_string(),
+            InflateFailure::Code(code) => format!("Inflate error: {:?}", code),
+            InflateFailure::OutputLimit => "Output size limit exceeded".to_string(),
+        }
+    }
+}
+
+pub(crate) struct InflateRun {
+    /// How much of the input inflate consumed
+    pub(crate) bytes_consumed: usize,
+    /// Whether inflate reached the end of the stream
+    pub(crate) stream_end: bool,
+    /// Set if inflate stopped because of an error
+    pub(crate) failure: Option<InflateFailure>,
+}
+
+/// Feeds `input` through inflate, appending the output to `output_buffer` one `temp_out_buf` sized chunk at a time.
+///
+/// Stops with [`InflateFailure::OutputLimit`] as soon as `output_buffer` grows past `output_limit`.
+pub(crate) fn inflate_input(
+    stream: &mut z_stream,
+    input: &[u8],
+    temp_out_buf: &mut [u8],
+    output_buffer: &mut Vec<u8>,
+    output_limit: Option<usize>,
+) -> Result<InflateRun> {
+    let mut input_chunk: &[u8] = input;
+    let mut run = InflateRun {
+        bytes_consumed: 0,
+        stream_end: false,
+        failure: None,
+    };
+
+    // Runs at least once, so output inflate is still holding on to gets drained even when there's no input left
+    loop {
+        stream.next_in = input_chunk.as_ptr() as *mut u8;
+        stream.avail_in = input_chunk
+            .len()
+            .try_into()
+            .map_err(|_| InflaterError::failure("Input chunk too large"))?;
+
+        loop {
+            stream.next_out = temp_out_buf.as_mut_ptr();
+            stream.avail_out = temp_out_buf
+                .len()
+                .try_into()
+                .map_err(|_| InflaterError::failure("Output chunk size too large"))?;
+
+            let total_out_before_inflate = stream.total_out;
+            let avail_in_before_inflate = stream.avail_in;
+
+            // SAFETY: Our pointers are all valid
+            let result_code = match unsafe { InflateStream::from_stream_mut(stream) } {
+                Some(inflate_stream_ref) => unsafe {
+                    inflate::inflate(inflate_stream_ref, InflateFlush::NoFlush)
+                },
+                None => {
+                    run.failure = Some(InflateFailure::InvalidStream);
+                    return Ok(run);
+                }
+            };
+
+            let written_in_call = (stream.total_out - total_out_before_inflate) as usize;
+            if written_in_call > 0 {
+                let actual_written = std::cmp::min(written_in_call, temp_out_buf.len());
+                output_buffer.extend_from_slice(&temp_out_buf[..actual_written]);
+            }
+
+            if exceeds_limit(output_buffer, output_limit) {
+                run.failure = Some(InflateFailure::OutputLimit);
+                return Ok(run);
+            }
+
+            let consumed_in_call = (avail_in_before_inflate - stream.avail_in) as usize;
+            input_chunk = &input_chunk[consumed_in_call..];
+            run.bytes_consumed += consumed_in_call;
+
+            match result_code {
+                ReturnCode::Ok => {
+                    if stream.avail_out == 0 {
+                        continue;
+                    }
+
+                    break;
+                }
+                ReturnCode::StreamEnd => {
+                    run.stream_end = true;
+                    return Ok(run);
+                }
+                // Should not happen with NoFlush, treat as unexpected or break
+                ReturnCode::BufError => {
+                    // Assume it means output buffer is full
+                    if stream.avail_out == 0 {
+                        continue;
+                    }
+                    break;
+                }
+                other_code => {
+                    run.failure = Some(InflateFailure::Code(other_code));
+                    return Ok(run);
+                }
+            }
+        }
+
+        if input_chunk.is_empty() {
+            return Ok(run);
+        }
+    }
+}
+
+thread_local! {
+    // Scratch space inflate writes into before its output is appended to the buffer handed back to JS. Only the
+    // main thread runs decompressors, so in practice this is a single allocation reused by all of them.
+    static SCRATCH_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Hands `f` a scratch buffer of `len` bytes, reusing the calling thread's allocation instead of allocating a new one
+/// for every call. The buffer's contents are left over from previous calls.
+pub(crate) fn with_scratch_buffer<T>(len: usize, f: impl FnOnce(&mut [u8]) -> T) -> T {
+    SCRATCH_BUFFER.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        if scratch.len() < len {
+            scratch.resize(len, 0);
+        }
+
+        f(&mut scratch[..len])
+    })
+}
+
+/// Runs inflate exactly once over `input`, writing up to `output_len` bytes straight into `output_buffer`'s spare
+/// capacity instead of going through a temporary buffer.
+///
+/// Also returns whether inflate may have more to do, i.e. it didn't get through all of `input` or filled the whole
+/// output, in which case the caller has to carry on with [`inflate_input`].
+pub(crate) fn inflate_single_call(
+    stream: &mut z_stream,
+    input: &[u8],
+    output_len: usize,
+    output_buffer: &mut Vec<u8>,
+) -> Result<(InflateRun, bool)> {
+    let mut run = InflateRun {
+        bytes_consumed: 0,
+        stream_end: false,
+        failure: None,
+    };
+
+    output_buffer.reserve(output_len);
+    let output_start = output_buffer.len();
+
+    stream.next_in = input.as_ptr() as *mut u8;
+    stream.avail_in = input
+        .len()
+        .try_into()
+        .map_err(|_| InflaterError::failure("Input chunk too large"))?;
+    // SAFETY: The reserve above guarantees room for output_len bytes past the current length
+    stream.next_out = unsafe { output_buffer.as_mut_ptr().add(output_start) };
+    stream.avail_out = output_len
+        .try_into()
+        .map_err(|_| InflaterError::failure("Output chunk size too large"))?;
+
+    // SAFETY: Our pointers are all valid
+    let result_code = match unsafe { InflateStream::from_stream_mut(stream) } {
+        Some(inflate_stream_ref) => unsafe {
+            inflate::inflate(inflate_stream_ref, InflateFlush::NoFlush)
+        },
+        None => {
+            run.failure = Some(InflateFailure::InvalidStream);
+            return Ok((run, false));
+        }
+    };
+
+    let written_in_call = output_len - stream.avail_out as usize;
+    // SAFETY: inflate initialized exactly this many bytes of the spare capacity
+    unsafe { output_buffer.set_len(output_start + written_in_call) };
+    run.bytes_consumed = input.len() - stream.avail_in as usize;
+
+    match result_code {
+        ReturnCode::StreamEnd => {
+            run.stream_end = true;
+            Ok((run, false))
+        }
+        ReturnCode::Ok | ReturnCode::BufError => {
+            let has_more = stream.avail_in != 0 || stream.avail_out == 0;
+            Ok((run, has_more))
+        }
+        other_code => {
+            run.failure = Some(InflateFailure::Code(other_code));
+            Ok((run, false))
+        }
+    }
+}
+
+/// Whether `output_buffer` has grown past `output_limit`, if there is one.
+fn exceeds_limit(output_buffer: &[u8], output_limit: Option<usize>) -> bool {
+    output_limit.is_some_and(|limit| output_buffer.len() > limit)
+}
+
+/// Whether inflate stopped because the stream asks for a preset dictionary.
+fn needs_dictionary(run: &InflateRun) -> bool {
+    matches!(
+        run.failure,
+        Some(InflateFailure::Code(ReturnCode::NeedDict))
+    )
+}
+
+/// Hands `dictionary` over to inflate, returning why it was rejected if it was.
+fn set_dictionary(stream: &mut z_stream, dictionary: &[u8]) -> Option<InflateFailure> {
+    // SAFETY: stream is an initialized inflate stream
+    let Some(inflate_stream_ref) = (unsafe { InflateStream::from_stream_mut(stream) }) else {
+        return Some(InflateFailure::InvalidStream);
+    };
+
+    match inflate::set_dictionary(inflate_stream_ref, dictionary) {
+        ReturnCode::Ok => None,
+        other_code => Some(InflateFailure::Code(other_code)),
+    }
+}
+
+/// Like [`inflate_input`], but supplies `dictionary` and carries on when a zlib stream asks for one.
+fn inflate_input_with_dictionary(
+    stream: &mut z_stream,
+    input: &[u8],
+    temp_out_buf: &mut [u8],
+    output_buffer: &mut Vec<u8>,
+    output_limit: Option<usize>,
+    dictionary: Option<&[u8]>,
+) -> Result<InflateRun> {
+    let mut run = inflate_input(stream, input, temp_out_buf, output_buffer, output_limit)?;
+
+    // Streams only ever ask for their dictionary once, right after the header
+    if let Some(dictionary) = dictionary.filter(|_| needs_dictionary(&run)) {
+        run.failure = set_dictionary(stream, dictionary);
+
+        if run.failure.is_none() {
+            let rest = inflate_input(
+                stream,
+                &input[run.bytes_consumed..],
+                temp_out_buf,
+                output_buffer,
+                output_limit,
+            )?;
+
+            run.bytes_consumed += rest.bytes_consumed;
+            run.stream_end = rest.stream_end;
+            run.failure = rest.failure;
+        }
+    }
+
+    Ok(run)
+}
+
+/// Runs `f` on a throwaway copy of `stream`, which is torn down again afterwards.
+///
+/// `stream` itself is left exactly as it was, including the pointers and counts it was last left with.
+fn with_stream_copy<T>(
+    stream: &mut z_stream,
+    f: impl FnOnce(&mut z_stream) -> Result<T>,
+) -> Result<T> {
+    let mut lent_out_buf = [0u8; 1];
+
+    // inflateCopy refuses to copy a stream that has never been given an output buffer, so one is lent to the stream
+    // just for the copy. Everything it touches is put back afterwards, leaving inputRemaining, the debug dump and the
+    // next push exactly as they were.
+    let saved = (
+        stream.next_in,
+        stream.avail_in,
+        stream.next_out,
+        stream.avail_out,
+    );
+    if stream.next_out.is_null() {
+        stream.next_out = lent_out_buf.as_mut_ptr();
+        stream.avail_out = 0;
+    }
+
+    let mut copy = Box::new(MaybeUninit::<InflateStream>::uninit());
+    let ret_code = match unsafe { InflateStream::from_stream_mut(stream) } {
+        // SAFETY: source is a valid, initialized inflate stream
+        Some(source) => unsafe { inflate::copy(&mut copy, source) },
+        None => ReturnCode::StreamError,
+    };
+
+    (
+        stream.next_in,
+        stream.avail_in,
+        stream.next_out,
+        stream.avail_out,
+    ) = saved;
+
+    if ret_code != ReturnCode::Ok {
+        return Err(InflaterError::failure(format!(
+            "Failed to copy inflate stream: {:?}",
+            ret_code
+        )));
+    }
+
+    // SAFETY: inflate::copy fully initialized the copy, and InflateStream has the same layout as z_stream
+    let copy_stream = unsafe { &mut *copy.as_mut_ptr().cast::<z_stream>() };
+
+    let result = f(copy_stream);
+
+    // SAFETY: The copy is still initialized, so it is safe to tear it down
+    if let Some(copy_ref) = unsafe { InflateStream::from_stream_mut(copy_stream) } {
+        inflate::end(copy_ref);
+    }
+
+    result
+}
+
+/// Appends `value` to `out` as a JSON string literal.
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+
+    for character in value.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            control if control < ' ' => out.push_str(&format!("\\u{:04x}", control as u32)),
+            other => out.push(other),
+        }
+    }
+
+    out.push('"');
+}
+
+/// A zlib inflate stream fed sync-flushed messages, which is everything `ZlibDecompressor` does minus the bindings.
+pub struct Inflater {
+    chunk_size: u32,
+    // The window bits the stream was last initialized with
+    window_bits: i32,
+    // Pointer to the heap-allocated z_stream
+    stream_ptr: NonNull<z_stream>,
+    // Buffer for incoming data until Z_SYNC_FLUSH
+    internal_buffer: Vec<u8>,
+    // Track finished state separately (for terminal errors or unexpected StreamEnd)
+    finished: bool,
+    // Copy of z_stream.msg from the last inflate error, if zlib provided one
+    last_error: Option<String>,
+    // Preset dictionary the stream was compressed with
+    dictionary: Option<Vec<u8>>,
+    // How much output a single call may produce before the stream is given up on
+    max_output_size: Option<u32>,
+    // How long the last push spent inflating, in nanoseconds
+    last_push_ns: u64,
+    // Set once dispose has torn the stream down
+    disposed: bool,
+    // Where the sync-flush suffix started in the last message taken out of internal_buffer
+    last_suffix_position: Option<u32>,
+    // How many sync-flushed messages push, safeDecompress and decompressFrame inflated successfully
+    frames_processed: u64,
+}
+
+impl Drop for Inflater {
+    fn drop(&mut self) {
+        // SAFETY: NonNull guarantees that the stream_ptr is valid. Additionally, since this is the Drop trait,
+        // we should have no problems with double-frees or dangling pointers.
+        unsafe {
+            if let Some(inflate_stream_ref) =
+                InflateStream::from_stream_mut(self.stream_ptr.as_ptr())
+            {
+                inflate::end(inflate_stream_ref);
+            }
+
+            let _ = Box::from_raw(self.stream_ptr.as_ptr());
+        }
+    }
+}
+
+impl Inflater {
+    /// Creates an inflater from an options object.
+    pub fn with_options(options: InflaterOptions) -> Result<Self> {
+        let chunk_size = validate_chunk_size(options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE))?;
+
+        let mut config = InflateConfig::default(); // Use default window bits unless told otherwise
+        if let Some(window_bits) = options.window_bits {
+            config.window_bits = validate_window_bits(window_bits)?;
+        }
+
+        let mut stream = Box::new(z_stream::default());
+
+        // Initialize the stream for inflation
+        let ret_code = inflate::init(&mut stream, config);
+        if ret_code != ReturnCode::Ok {
+            return Err(InflaterError::failure(format!(
+                "Failed to initialize inflate stream: {:?}",
+                ret_code
+            )));
+        }
+
+        let stream_ptr = NonNull::new(Box::into_raw(stream)).ok_or_else(|| {
+            // If this fails, something is very wrong (Box::into_raw returning null?)
+            // We might need some manual deallocation logic here, but it's very complex so let's just pray for the best.
+            InflaterError::failure("Failed to get stream pointer after init")
+        })?;
+
+        let mut inflater = Self {
+            stream_ptr,
+            chunk_size,
+            window_bits: config.window_bits,
+            internal_buffer: Vec::new(),
+            finished: false,
+            last_error: None,
+            dictionary: options.dictionary,
+            max_output_size: options.max_output_size,
+            last_push_ns: 0,
+            disposed: false,
+            last_suffix_position: None,
+            frames_processed: 0,
+        };
+        inflater.preset_raw_dictionary()?;
+
+        Ok(inflater)
+    }
+
+    /// Creates an inflater whose LZ77 window holds at least `n_bytes` bytes of history, rounded up to the next valid
+    /// window size. `n_bytes` must be within 1-32768.
+    pub fn with_lz77_history_size(chunk_size: u32, n_bytes: u32) -> Result<Self> {
+        if !(1..=32768).contains(&n_bytes) {
+            return Err(InflaterError::invalid_arg(format!(
+                "Invalid LZ77 history size {}, expected 1-32768",
+                n_bytes
+            )));
+        }
+
+        let window_bits = n_bytes.next_power_of_two().trailing_zeros().max(8) as i32;
+
+        Self::with_options(InflaterOptions {
+            chunk_size: Some(chunk_size),
+            window_bits: Some(window_bits),
+            ..InflaterOptions::default()
+        })
+    }
+
+    /// Creates an inflater that detects whether the stream is zlib or gzip-wrapped by itself (47 window bits).
+    pub fn auto_detect(chunk_size: Option<u32>) -> Result<Self> {
+        Self::with_options(InflaterOptions {
+            chunk_size,
+            window_bits: Some(AUTO_DETECT_WINDOW_BITS),
+            ..InflaterOptions::default()
+        })
+    }
+
+    /// Fails with `Decompressor has been disposed` once `dispose` was called, for the methods that throw.
+    fn ensure_not_disposed(&self) -> Result<()> {
+        if self.disposed {
+            return Err(InflaterError::failure(DECOMPRESSOR_DISPOSED));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the stream ended or hit a terminal error, after which `push` ignores any further input.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The `maxOutputSize` option, if set.
+    fn output_limit(&self) -> Option<usize> {
+        self.max_output_size.map(|limit| limit as usize)
+    }
+
+    /// Raw deflate streams have no header to ask for the dictionary with, so it has to be handed over up front.
+    fn preset_raw_dictionary(&mut self) -> Result<()> {
+        let Some(dictionary) = self.dictionary.as_deref().filter(|_| self.window_bits < 0) else {
+            return Ok(());
+        };
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        match set_dictionary(stream, dictionary) {
+            None => Ok(()),
+            Some(failure) => Err(InflaterError::failure(format!(
+                "Failed to set dictionary: {}",
+                failure.message()
+            ))),
+        }
+    }
+
+    /// Marks the stream as finished after an inflate failure, keeping zlib's message around for
+    /// `lastErrorMessage`. Returns the error to hand back to the caller.
+    fn record_failure(&mut self, failure: InflateFailure) -> String {
+        self.finished = true;
+
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        let stream = unsafe { self.stream_ptr.as_ref() };
+        if matches!(failure, InflateFailure::Code(_)) && !stream.msg.is_null() {
+            // SAFETY: zlib only ever points msg at static, NUL-terminated strings
+            let message = unsafe { CStr::from_ptr(stream.msg) };
+            self.last_error = Some(message.to_string_lossy().into_owned());
+        }
+
+        failure.message()
+    }
+
+    /// Finds where the first complete message in `internal_buffer` ends, just past its sync-flush suffix.
+    ///
+    /// A suffix ending the buffer is trusted like `push` trusts it. Any earlier one is only taken once inflating up to
+    /// it through a copy of the stream lands on a block boundary, or on the end of the stream or an error, which the
+    /// real inflate then reports.
+    fn next_frame_end(&mut self) -> Result<Option<usize>> {
+        let suffix_len = Z_SYNC_FLUSH_SUFFIX.len();
+        let mut search_from = 0;
+
+        while let Some(offset) = self.internal_buffer[search_from..]
+            .windows(suffix_len)
+            .position(|window| window == Z_SYNC_FLUSH_SUFFIX)
+        {
+            let message_end = search_from + offset + suffix_len;
+            if message_end == self.internal_buffer.len() {
+                return Ok(Some(message_end));
+            }
+
+            // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+            let stream = unsafe { self.stream_ptr.as_mut() };
+            let (message, last_byte) =
+                self.internal_buffer[..message_end].split_at(message_end - 1);
+            let chunk_size = self.chunk_size as usize;
+            let output_limit = self.output_limit();
+            let dictionary = self.dictionary.as_deref();
+
+            let at_boundary = with_stream_copy(stream, |copy| {
+                let mut temp_out_buf = vec![0u8; chunk_size];
+                let mut output_buffer = Vec::new();
+
+                let run = inflate_input_with_dictionary(
+                    copy,
+                    message,
+                    &mut temp_out_buf,
+                    &mut output_buffer,
+                    output_limit,
+                    dictionary,
+                )?;
+
+                // Leave the rest to the real inflate, which reports the error or the end of the stream itself
+                if run.failure.is_some() || run.stream_end {
+                    return Ok(true);
+                }
+
+                // Inflate only stops between two blocks when asked to with Z_BLOCK, so the last byte goes in on its
+                // own with it
+                copy.next_in = last_byte.as_ptr() as *mut u8;
+                copy.avail_in = 1;
+                copy.next_out = temp_out_buf.as_mut_ptr();
+                copy.avail_out = temp_out_buf.len() as u32;
+
+                // SAFETY: Our pointers are all valid
+                let ret_code = match unsafe { InflateStream::from_stream_mut(copy) } {
+                    Some(inflate_stream_ref) => unsafe {
+                        inflate::inflate(inflate_stream_ref, InflateFlush::Block)
+                    },
+                    None => ReturnCode::StreamError,
+                };
+
+                Ok(ret_code != ReturnCode::Ok
+                    || (copy.avail_in == 0 && copy.data_type == AT_BLOCK_BOUNDARY))
+            })?;
+
+            if at_boundary {
+                return Ok(Some(message_end));
+            }
+
+            search_from = message_end;
+        }
+
+        Ok(None)
+    }
+
+    /// Inflates one complete sync-flushed message taken out of the internal buffer, which is what `push` and
+    /// `decompressFrame` share.
+    fn inflate_message(&mut self, decompress: Vec<u8>) -> Result<PushOutcome> {
+        self.last_suffix_position = Some((decompress.len() - Z_SYNC_FLUSH_SUFFIX.len()) as u32);
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let chunk_size = self.chunk_size as usize;
+        let dictionary = self.dictionary.as_deref();
+        let output_limit = self.output_limit();
+        let mut output_buffer = Vec::new();
+
+        // std::time::Instant panics on wasm32-unknown-unknown, which has no clock to read
+        #[cfg(not(target_family = "wasm"))]
+        let started = std::time::Instant::now();
+
+        // Most messages are small enough to inflate in one call, in which case there's no need for the chunked loop
+        // and its temporary buffer
+        let run = if decompress.len() <= chunk_size {
+            let (mut run, has_more) =
+                inflate_single_call(stream, &decompress, chunk_size, &mut output_buffer)?;
+
+            if has_more || (dictionary.is_some() && needs_dictionary(&run)) {
+                let rest = with_scratch_buffer(chunk_size, |temp_out_buf| {
+                    inflate_input_with_dictionary(
+                        stream,
+                        &decompress[run.bytes_consumed..],
+                        temp_out_buf,
+                        &mut output_buffer,
+                        output_limit,
+                        dictionary,
+                    )
+                })?;
+
+                run.bytes_consumed += rest.bytes_consumed;
+                run.stream_end = rest.stream_end;
+                run.failure = rest.failure;
+            }
+
+            run
+        } else {
+            with_scratch_buffer(chunk_size, |temp_out_buf| {
+                inflate_input_with_dictionary(
+                    stream,
+                    &decompress,
+                    temp_out_buf,
+                    &mut output_buffer,
+                    output_limit,
+                    dictionary,
+                )
+            })?
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            self.last_push_ns = started.elapsed().as_nanos() as u64;
+        }
+
+        // The single-call path doesn't check the limit itself
+        let failure = run.failure.or_else(|| {
+            exceeds_limit(&output_buffer, output_limit).then_some(InflateFailure::OutputLimit)
+        });
+
+        if let Some(failure) = failure {
+            return Ok(PushOutcome::Failed(self.record_failure(failure)));
+        }
+
+        // Discord shouldn't do this, but we handle it regardless
+        if run.stream_end {
+            self.finished = true;
+        }
+
+        self.frames_processed += 1;
+
+        Ok(PushOutcome::ok(run.bytes_consumed, output_buffer))
+    }
+
+    /// The LZ77 history inflate currently holds, oldest byte first, or `None` once the stream was torn down.
+    ///
+    /// inflate keeps its window as a ring buffer, so this is a copy put back in order rather than a borrow of it.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn history_buffer(&self) -> Option<Vec<u8>> {
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        let stream = unsafe { InflateStream::from_stream_ref(self.stream_ptr.as_ptr()) }?;
+
+        // SAFETY: Passing a null pointer only asks for the length
+        let len = unsafe { inflate::get_dictionary(stream, std::ptr::null_mut()) };
+
+        let mut history = vec![0u8; len];
+        // SAFETY: history has room for the len bytes get_dictionary copies into it
+        unsafe { inflate::get_dictionary(stream, history.as_mut_ptr()) };
+
+        Some(history)
+    }
+
+    /// Switches the stream over to `bits` window bits, resetting it (reusing its allocations). Any buffered input and
+    /// history is discarded, and a finished inflater becomes usable again.
+    pub fn set_window_bits(&mut self, bits: i32) -> Result<()> {
+        self.ensure_not_disposed()?;
+
+        let config = InflateConfig {
+            window_bits: validate_window_bits(bits)?,
+        };
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+        let inflate_stream_ref = unsafe { InflateStream::from_stream_mut(stream) }
+            .ok_or_else(|| InflaterError::failure("Failed to get inflate stream reference"))?;
+
+        let ret_code = inflate::reset_with_config(inflate_stream_ref, config);
+        if ret_code != ReturnCode::Ok {
+            return Err(InflaterError::failure(format!(
+                "Failed to reset inflate stream: {:?}",
+                ret_code
+            )));
+        }
+
+        self.window_bits = config.window_bits;
+        self.internal_buffer.clear();
+        self.finished = false;
+        self.last_error = None;
+
+        self.preset_raw_dictionary()
+    }
+
+    /// Sets or removes how many bytes a single call may produce, taking effect from the next call on.
+    pub fn set_output_limit(&mut self, bytes: Option<u32>) {
+        self.max_output_size = bytes;
+    }
+
+    /// Buffers `data` until a full sync-flushed message is available, then inflates it.
+    pub fn push(&mut self, data: &[u8]) -> Result<PushOutcome> {
+        self.last_push_ns = 0;
+
+        if self.disposed {
+            return Ok(PushOutcome::Failed(DECOMPRESSOR_DISPOSED.to_string()));
+        }
+
+        if self.finished {
+            // Already finished (due to error or StreamEnd), return early
+            return Ok(PushOutcome::ok(0, Vec::new()));
+        }
+
+        // Append new data to the internal buffer
+        self.internal_buffer.extend_from_slice(data);
+
+        // Check if the buffer ends with the Z_SYNC_FLUSH suffix
+        if !self.internal_buffer.ends_with(Z_SYNC_FLUSH_SUFFIX) {
+            return Ok(PushOutcome::ok(0, Vec::new()));
+        }
+
+        // Flush suffix; take the buffer content for decompression
+        let decompress = std::mem::take(&mut self.internal_buffer);
+        self.inflate_message(decompress)
+    }
+
+    /// Inflates the next complete sync-flushed message still sitting in the internal buffer, or returns `None` if
+    /// more data is needed. Inflate failures are returned as errors and finish the inflater.
+    pub fn decompress_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        self.ensure_not_disposed()?;
+
+        if self.finished {
+            return Ok(None);
+        }
+
+        let Some(message_end) = self.next_frame_end()? else {
+            return Ok(None);
+        };
+
+        let rest = self.internal_buffer.split_off(message_end);
+        let message = std::mem::replace(&mut self.internal_buffer, rest);
+
+        match self.inflate_message(message)? {
+            PushOutcome::Ok { output, .. } => Ok(Some(output)),
+            PushOutcome::Failed(message) => Err(InflaterError::failure(message)),
+        }
+    }
+
+    /// Like `push`, but gives up on the stream once its running expansion ratio goes above `max_ratio`.
+    pub fn safe_decompress(&mut self, data: &[u8], max_ratio: f64) -> Result<PushOutcome> {
+        if self.disposed {
+            return Ok(PushOutcome::Failed(DECOMPRESSOR_DISPOSED.to_string()));
+        }
+
+        if max_ratio.is_nan() || max_ratio <= 0.0 {
+            return Err(InflaterError::invalid_arg(format!(
+                "Invalid max ratio {}, expected a positive number",
+                max_ratio
+            )));
+        }
+
+        if self.finished {
+            // Already finished (due to error or StreamEnd), return early
+            return Ok(PushOutcome::ok(0, Vec::new()));
+        }
+
+        self.internal_buffer.extend_from_slice(data);
+
+        if !self.internal_buffer.ends_with(Z_SYNC_FLUSH_SUFFIX) {
+            return Ok(PushOutcome::ok(0, Vec::new()));
+        }
+
+        let decompress = std::mem::take(&mut self.internal_buffer);
+        self.last_suffix_position = Some((decompress.len() - Z_SYNC_FLUSH_SUFFIX.len()) as u32);
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let mut output_buffer = Vec::new();
+        let mut input_chunk = decompress.as_slice();
+        let mut bytes_consumed = 0;
+
+        loop {
+            let (run, has_more) = inflate_single_call(
+                stream,
+                input_chunk,
+                self.chunk_size as usize,
+                &mut output_buffer,
+            )?;
+
+            input_chunk = &input_chunk[run.bytes_consumed..];
+            bytes_consumed += run.bytes_consumed;
+
+            if let Some(dictionary) = self
+                .dictionary
+                .as_deref()
+                .filter(|_| needs_dictionary(&run))
+            {
+                match set_dictionary(stream, dictionary) {
+                    None => continue,
+                    Some(failure) => return Ok(PushOutcome::Failed(self.record_failure(failure))),
+                }
+            }
+
+            if let Some(failure) = run.failure {
+                return Ok(PushOutcome::Failed(self.record_failure(failure)));
+            }
+
+            if exceeds_limit(&output_buffer, self.output_limit()) {
+                return Ok(PushOutcome::Failed(
+                    self.record_failure(InflateFailure::OutputLimit),
+                ));
+            }
+
+            if stream.total_out as f64 > stream.total_in as f64 * max_ratio {
+                self.finished = true;
+                return Ok(PushOutcome::Failed("ratio limit exceeded".to_string()));
+            }
+
+            if run.stream_end {
+                self.finished = true;
+                break;
+            }
+
+            if !has_more {
+                break;
+            }
+        }
+
+        self.frames_processed += 1;
+
+        Ok(PushOutcome::ok(bytes_consumed, output_buffer))
+    }
+
+    /// How long the last `push` or `decompress_frame` call spent inflating, in nanoseconds. Always 0 on WebAssembly,
+    /// which has no clock to read.
+    pub fn last_push_duration_ns(&self) -> u64 {
+        self.last_push_ns
+    }
+
+    /// How many sync-flushed messages have been inflated successfully.
+    pub fn frames_processed(&self) -> u64 {
+        self.frames_processed
+    }
+
+    /// Inflates a message that arrived in several fragments in one go, after anything already buffered. No
+    /// sync-flush suffix is required, and failures are returned as errors.
+    pub fn inflate_coalesce(&mut self, fragments: &[&[u8]]) -> Result<Vec<u8>> {
+        self.ensure_not_disposed()?;
+
+        if self.finished {
+            return Err(InflaterError::failure("Decompressor has already finished"));
+        }
+
+        let pending = std::mem::take(&mut self.internal_buffer);
+        let total_input_len = pending.len()
+            + fragments
+                .iter()
+                .map(|fragment| fragment.len())
+                .sum::<usize>();
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        // The input size is a decent lower bound for how much inflate will produce
+        let mut output_buffer = Vec::with_capacity(total_input_len);
+        let output_limit = self.output_limit();
+
+        for fragment in std::iter::once(pending.as_slice()).chain(fragments.iter().copied()) {
+            let run = with_scratch_buffer(self.chunk_size as usize, |temp_out_buf| {
+                inflate_input_with_dictionary(
+                    stream,
+                    fragment,
+                    temp_out_buf,
+                    &mut output_buffer,
+                    output_limit,
+                    self.dictionary.as_deref(),
+                )
+            })?;
+
+            if let Some(failure) = run.failure {
+                return Err(InflaterError::failure(self.record_failure(failure)));
+            }
+
+            if run.stream_end {
+                self.finished = true;
+                break;
+            }
+        }
+
+        Ok(output_buffer)
+    }
+
+    /// How many bytes are buffered while waiting for a sync-flush suffix.
+    pub fn input_buffer_len(&self) -> u32 {
+        self.internal_buffer.len() as u32
+    }
+
+    /// Discards the input buffered while waiting for a sync-flush suffix, leaving the stream itself untouched.
+    pub fn clear_buffer(&mut self) {
+        self.internal_buffer.clear();
+    }
+
+    /// Where the sync-flush suffix started in the last complete message inflated, if there was one.
+    pub fn suffix_position(&self) -> Option<u32> {
+        self.last_suffix_position
+    }
+
+    /// How much input the last inflate call left unconsumed (the stream's `avail_in`).
+    pub fn input_remaining(&self) -> u32 {
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        unsafe { self.stream_ptr.as_ref() }.avail_in
+    }
+
+    /// The message zlib reported for the last inflate error, if any.
+    pub fn last_error_message(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Checks whether pushing `data` would succeed by inflating the buffered input plus `data` through a copy of the
+    /// stream, leaving this inflater untouched. Returns `false` if it has already finished.
+    pub fn test_push(&mut self, data: &[u8]) -> Result<bool> {
+        self.ensure_not_disposed()?;
+
+        if self.finished {
+            return Ok(false);
+        }
+
+        let mut pending = Vec::with_capacity(self.internal_buffer.len() + data.len());
+        pending.extend_from_slice(&self.internal_buffer);
+        pending.extend_from_slice(data);
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+
+        let chunk_size = self.chunk_size as usize;
+        let output_limit = self.output_limit();
+        let dictionary = self.dictionary.as_deref();
+
+        let run = with_stream_copy(stream, |copy| {
+            let mut temp_out_buf = vec![0u8; chunk_size];
+            let mut output_buffer = Vec::new();
+
+            inflate_input_with_dictionary(
+                copy,
+                &pending,
+                &mut temp_out_buf,
+                &mut output_buffer,
+                output_limit,
+                dictionary,
+            )
+        })?;
+
+        Ok(run.failure.is_none())
+    }
+
+    /// The inflater's current state as a single-line JSON object, for logging.
+    pub fn metrics_json(&self) -> String {
+        let stats = self.stats();
+
+        let mut json = format!(
+            r#"{{"totalIn":{},"totalOut":{},"ratio":{},"finished":{},"error":"#,
+            stats.total_in, stats.total_out, stats.ratio, self.finished
+        );
+
+        match &self.last_error {
+            Some(message) => write_json_string(&mut json, message),
+            None => json.push_str("null"),
+        }
+
+        json.push_str(&format!(
+            r#","chunkSize":{},"windowBits":{}}}"#,
+            self.chunk_size, self.window_bits
+        ));
+
+        json
+    }
+
+    /// Whether `other` has the same window bits and chunk size, and neither of them has finished or hit an error.
+    pub fn compatible_with(&self, other: &Inflater) -> bool {
+        self.window_bits == other.window_bits
+            && self.chunk_size == other.chunk_size
+            && !self.finished
+            && !other.finished
+    }
+
+    /// A human-readable dump of the inflater's internal state, for crash reports. Pointers are only printed, never
+    /// followed.
+    pub fn create_debug_dump(&self) -> String {
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        let stream = unsafe { self.stream_ptr.as_ref() };
+
+        format!(
+            "ZlibDecompressor {{\n  \
+               stream: {:p}\n  \
+               state: {:p}\n  \
+               next_in: {:p}\n  \
+               avail_in: {}\n  \
+               next_out: {:p}\n  \
+               avail_out: {}\n  \
+               total_in: {}\n  \
+               total_out: {}\n  \
+               buffered_input: {}\n  \
+               chunk_size: {}\n  \
+               window_bits: {}\n  \
+               finished: {}\n  \
+               error: {}\n\
+             }}",
+            self.stream_ptr,
+            stream.state,
+            stream.next_in,
+            stream.avail_in,
+            stream.next_out,
+            stream.avail_out,
+            stream.total_in,
+            stream.total_out,
+            self.internal_buffer.len(),
+            self.chunk_size,
+            self.window_bits,
+            self.finished,
+            self.last_error.as_deref().unwrap_or("none"),
+        )
+    }
+
+    /// Skips ahead to the next flush point in the buffered input followed by `data` (`inflateSync`), keeping the input
+    /// after it buffered. Finding one makes a finished inflater usable again.
+    pub fn inflate_sync(&mut self, data: &[u8]) -> Result<SyncOutcome> {
+        self.ensure_not_disposed()?;
+
+        let mut pending = std::mem::take(&mut self.internal_buffer);
+        pending.extend_from_slice(data);
+
+        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
+        let stream = unsafe { self.stream_ptr.as_mut() };
+        stream.next_in = pending.as_mut_ptr();
+        stream.avail_in = pending
+            .len()
+            .try_into()
+            .map_err(|_| InflaterError::failure("Input chunk too large"))?;
+
+        let inflate_stream_ref = unsafe { InflateStream::from_stream_mut(stream) }
+            .ok_or_else(|| InflaterError::failure("Failed to get inflate stream reference"))?;
+
+        let ret_code = inflate::sync(inflate_stream_ref);
+        let bytes_skipped = pending.len() - stream.avail_in as usize;
+
+        if ret_code != ReturnCode::Ok {
+            return Ok(SyncOutcome {
+                found: false,
+                bytes_skipped: bytes_skipped as u32,
+            });
+        }
+
+        pending.drain(..bytes_skipped);
+        self.internal_buffer = pending;
+        self.finished = false;
+        self.last_error = None;
+
+        Ok(SyncOutcome {
+            found: true,
+            bytes_skipped: bytes_skipped as u32,
+        })
+    }
+
+    /// Snapshot of the stream's counters, read in a single call.
+    // The counters are c_ulong, which is only 32 bits wide on some targets (like wasm32), so the casts aren't always
+    // no-ops
+    #[allow(clippy::unnecessary_cast)]
+    pub fn stats(&self) -> InflaterStats {
+        // SAFETY: stream_ptr is valid for as long as self is alive
+        let stream = unsafe { self.stream_ptr.as_ref() };
+
+        let total_in = stream.total_in as u64;
+        let total_out = stream.total_out as u64;
+
+        InflaterStats {
+            total_in,
+            total_out,
+            adler: stream.adler as u32,
+            ratio: if total_in > 0 {
+                total_out as f64 / total_in as f64
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// Tears the inflate stream down right away and drops any buffered input. Disposing more than once does nothing.
+    pub fn dispose(&mut self) {
+        if self.disposed {
+            return;
+        }
+
+        self.disposed = true;
+        self.finished = true;
+        self.internal_buffer = Vec::new();
+        self.dictionary = None;
+
+        // SAFETY: stream_ptr is valid. inflate::end leaves the stream's state null, which makes Drop skip tearing it
+        // down a second time
+        if let Some(inflate_stream_ref) =
+            unsafe { InflateStream::from_stream_mut(self.stream_ptr.as_ptr()) }
+        {
+            inflate::end(inflate_stream_ref);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deflate::{DeflateCompressor, FlushMode};
+    use crate::options::CompressorOptions;
+    use crate::result::PushResult;
+    use napi::bindgen_prelude::{Either, Uint8Array};
+
+    fn output(result: PushResult) -> Vec<u8> {
+        assert!(result.ok);
+        match result.data {
+            Either::A(buffer) => buffer.to_vec(),
+            Either::B(_) => Vec::new(),
+        }
+    }
+
+    /// Compresses `data` as the next message of the compressor's stream, ending it with a sync flush.
+    fn flush_message(compressor: &mut DeflateCompressor, data: &[u8]) -> Vec<u8> {
+        let mut compressed = output(compressor.push(Uint8Array::new(data.to_vec())).unwrap());
+        compressed.extend(output(compressor.finish(FlushMode::Sync).unwrap()));
+        assert!(compressed.ends_with(Z_SYNC_FLUSH_SUFFIX));
+
+        compressed
+    }
+
+    fn sync_flushed(data: &[u8]) -> Vec<u8> {
+        flush_message(&mut DeflateCompressor::new(None, None).unwrap(), data)
+    }
+
+    fn new_inflater() -> Inflater {
+        Inflater::with_options(InflaterOptions::default()).unwrap()
+    }
+
+    fn inflated(outcome: PushOutcome) -> Vec<u8> {
+        match outcome {
+            PushOutcome::Ok { output, .. } => output,
+            PushOutcome::Failed(message) => panic!("push failed: {}", message),
+        }
+    }
+
+    #[test]
+    fn history_buffer_holds_the_inflated_output() {
+        let message = b"hello history, hello history, hello history";
+        let compressed = sync_flushed(message);
+
+        let mut inflater = new_inflater();
+        assert_eq!(inflater.history_buffer().as_deref(), Some(&[][..]));
+
+        let outcome = inflater.push(&compressed).unwrap();
+        assert_eq!(outcome, PushOutcome::ok(compressed.len(), message.to_vec()));
+
+        assert_eq!(inflater.history_buffer().as_deref(), Some(&message[..]));
+
+        inflater.dispose();
+        assert_eq!(inflater.history_buffer(), None);
+        assert_eq!(
+            inflater.push(&compressed).unwrap(),
+            PushOutcome::Failed(DECOMPRESSOR_DISPOSED.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_a_chunk_size_of_0() {
+        let err = Inflater::with_options(InflaterOptions {
+            chunk_size: Some(0),
+            ..InflaterOptions::default()
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.kind, InflaterErrorKind::InvalidArg);
+
+        let err = Inflater::with_lz77_history_size(0, 1024).err().unwrap();
+        assert_eq!(err.kind, InflaterErrorKind::InvalidArg);
+
+        let err = Inflater::auto_detect(Some(0)).err().unwrap();
+        assert_eq!(err.kind, InflaterErrorKind::InvalidArg);
+    }
+
+    #[test]
+    fn push_buffers_until_the_sync_flush_suffix() {
+        let mut compressor = DeflateCompressor::new(None, None).unwrap();
+        let first = flush_message(&mut compressor, b"first message");
+        let second = flush_message(&mut compressor, b"second message");
+
+        let mut inflater = new_inflater();
+        let (head, tail) = first.split_at(first.len() - 2);
+
+        assert_eq!(inflater.push(head).unwrap(), PushOutcome::ok(0, Vec::new()));
+        assert_eq!(inflater.input_buffer_len(), head.len() as u32);
+
+        assert_eq!(
+            inflater.push(tail).unwrap(),
+            PushOutcome::ok(first.len(), b"first message".to_vec())
+        );
+        assert_eq!(inflater.input_buffer_len(), 0);
+        assert_eq!(inflater.suffix_position(), Some((first.len() - 4) as u32));
+
+        // The second message references the history left by the first one
+        assert_eq!(inflated(inflater.push(&second).unwrap()), b"second message");
+        assert_eq!(inflater.frames_processed(), 2);
+
+        let stats = inflater.stats();
+        assert_eq!(stats.total_in, (first.len() + second.len()) as u64);
+        assert_eq!(stats.total_out, 27);
+    }
+
+    #[test]
+    fn push_fails_on_malformed_data_and_finishes() {
+        let mut inflater = new_inflater();
+
+        let mut garbage = vec![0xff; 8];
+        garbage.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+
+        assert!(matches!(
+            inflater.push(&garbage).unwrap(),
+            PushOutcome::Failed(_)
+        ));
+        assert!(inflater.is_finished());
+        assert!(inflater.last_error_message().is_some());
+
+        // Further input is ignored once the stream has failed
+        assert_eq!(
+            inflater.push(&sync_flushed(b"ignored")).unwrap(),
+            PushOutcome::ok(0, Vec::new())
+        );
+    }
+
+    #[test]
+    fn decompress_frame_pulls_buffered_messages_one_at_a_time() {
+        let mut compressor = DeflateCompressor::new(None, None).unwrap();
+        let first = flush_message(&mut compressor, b"first message");
+        let second = flush_message(&mut compressor, b"second message");
+
+        let mut inflater = new_inflater();
+        assert_eq!(inflater.decompress_frame().unwrap(), None);
+
+        // The second message is cut short, so push leaves everything buffered
+        let mut pending = first.clone();
+        pending.extend_from_slice(&second[..second.len() - 1]);
+        assert_eq!(
+            inflater.push(&pending).unwrap(),
+            PushOutcome::ok(0, Vec::new())
+        );
+
+        assert_eq!(
+            inflater.decompress_frame().unwrap().as_deref(),
+            Some(&b"first message"[..])
+        );
+        assert_eq!(inflater.decompress_frame().unwrap(), None);
+        assert_eq!(inflater.input_buffer_len(), (second.len() - 1) as u32);
+
+        assert_eq!(
+            inflated(inflater.push(&second[second.len() - 1..]).unwrap()),
+            b"second message"
+        );
+        assert_eq!(inflater.frames_processed(), 2);
+    }
+
+    #[test]
+    fn decompress_frame_fails_once_disposed() {
+        let mut inflater = new_inflater();
+        inflater.dispose();
+
+        let err = inflater.decompress_frame().unwrap_err();
+        assert_eq!(err.kind, InflaterErrorKind::Failure);
+        assert_eq!(err.message, DECOMPRESSOR_DISPOSED);
+    }
+
+    #[test]
+    fn inflate_sync_resumes_after_corruption() {
+        let mut compressor = DeflateCompressor::new(None, None).unwrap();
+        let first = flush_message(&mut compressor, b"first message");
+
+        // A full flush resets the compression state, so the next message doesn't reference anything lost to corruption
+        let mut lost = output(
+            compressor
+                .push(Uint8Array::new(b"lost message".to_vec()))
+                .unwrap(),
+        );
+        lost.extend(output(compressor.finish(FlushMode::Full).unwrap()));
+        let second = flush_message(&mut compressor, b"second message");
+
+        let mut inflater = new_inflater();
+        assert_eq!(inflated(inflater.push(&first).unwrap()), b"first message");
+
+        let mut corrupted = vec![0xff; lost.len() - Z_SYNC_FLUSH_SUFFIX.len()];
+        corrupted.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+        assert!(matches!(
+            inflater.push(&corrupted).unwrap(),
+            PushOutcome::Failed(_)
+        ));
+
+        // Nothing to sync to in the data alone
+        let missed = inflater.inflate_sync(&[0xff; 16]).unwrap();
+        assert!(!missed.found);
+        assert_eq!(missed.bytes_skipped, 16);
+        assert!(inflater.is_finished());
+
+        let synced = inflater.inflate_sync(&corrupted).unwrap();
+        assert!(synced.found);
+        assert_eq!(synced.bytes_skipped, corrupted.len() as u32);
+        assert!(!inflater.is_finished());
+        assert_eq!(inflater.last_error_message(), None);
+
+        assert_eq!(inflated(inflater.push(&second).unwrap()), b"second message");
+    }
+
+    #[test]
+    fn set_window_bits_resets_the_stream() {
+        let mut raw_compressor = DeflateCompressor::new(
+            Some(Either::B(CompressorOptions {
+                window_bits: Some(-15),
+                ..CompressorOptions::default()
+            })),
+            None,
+        )
+        .unwrap();
+        let raw = flush_message(&mut raw_compressor, b"raw message");
+
+        let mut inflater = new_inflater();
+        assert_eq!(
+            inflater.push(b"left behind").unwrap(),
+            PushOutcome::ok(0, Vec::new())
+        );
+
+        inflater.set_window_bits(-15).unwrap();
+        assert_eq!(inflater.input_buffer_len(), 0);
+        assert_eq!(inflated(inflater.push(&raw).unwrap()), b"raw message");
+
+        let err = inflater.set_window_bits(7).unwrap_err();
+        assert_eq!(err.kind, InflaterErrorKind::InvalidArg);
+
+        // Switching back to zlib makes a failed stream usable again
+        let mut garbage = vec![0xff; 8];
+        garbage.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+        assert!(matches!(
+            inflater.push(&garbage).unwrap(),
+            PushOutcome::Failed(_)
+        ));
+
+        inflater.set_window_bits(15).unwrap();
+        assert!(!inflater.is_finished());
+        assert_eq!(
+            inflated(inflater.push(&sync_flushed(b"zlib message")).unwrap()),
+            b"zlib message"
+        );
+    }
+
+    #[test]
+    fn set_output_limit_applies_from_the_next_call() {
+        let message = vec![b'a'; 4096];
+        let compressed = sync_flushed(&message);
+
+        let mut inflater = new_inflater();
+        inflater.set_output_limit(Some(1024));
+        assert_eq!(
+            inflater.push(&compressed).unwrap(),
+            PushOutcome::Failed("Output size limit exceeded".to_string())
+        );
+        assert!(inflater.is_finished());
+
+        let mut inflater = new_inflater();
+        inflater.set_output_limit(Some(1024));
+        inflater.set_output_limit(None);
+        assert_eq!(inflated(inflater.push(&compressed).unwrap()), message);
+
+        let mut inflater = new_inflater();
+        inflater.set_output_limit(Some(4096));
+        assert_eq!(inflated(inflater.push(&compressed).unwrap()), message);
+    }
+}
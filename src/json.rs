@@ -0,0 +1,170 @@
+use crate::options::DecompressorOptions;
+use crate::text::TextDecompressor;
+use napi::bindgen_prelude::{Either, Result, Uint8Array};
+use napi::{Env, JsUnknown};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+
+/// Result of pushing data into a `JsonDecompressor`.
+///
+/// `value` is only there once a message was inflated, and `finished` tells whether the underlying stream has ended or
+/// hit a terminal error. Failed results carry `error`.
+#[napi(object, object_from_js = false)]
+pub struct JsonPushResult {
+    pub ok: bool,
+    pub value: Option<JsUnknown>,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+/// Builds JS values straight from the JSON being parsed, instead of going through `serde_json::Value` first. Object
+/// keys keep the order they appear in, just like with `JSON.parse`.
+struct JsValueSeed<'env>(&'env Env);
+
+fn js_error<E: de::Error>(err: napi::Error) -> E {
+    E::custom(err.reason)
+}
+
+impl<'de> DeserializeSeed<'de> for JsValueSeed<'_> {
+    type Value = JsUnknown;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<JsUnknown, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for JsValueSeed<'_> {
+    type Value = JsUnknown;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> std::result::Result<JsUnknown, E> {
+        self.0
+            .get_boolean(value)
+            .map(|value| value.into_unknown())
+            .map_err(js_error)
+    }
+
+    // Every number becomes a double, so integers past 2^53 lose precision exactly like they do with JSON.parse
+    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<JsUnknown, E> {
+        self.visit_f64(value as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<JsUnknown, E> {
+        self.visit_f64(value as f64)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> std::result::Result<JsUnknown, E> {
+        self.0
+            .create_double(value)
+            .map(|value| value.into_unknown())
+            .map_err(js_error)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<JsUnknown, E> {
+        self.0
+            .create_string(value)
+            .map(|value| value.into_unknown())
+            .map_err(js_error)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<JsUnknown, E> {
+        self.0
+            .get_null()
+            .map(|value| value.into_unknown())
+            .map_err(js_error)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<JsUnknown, A::Error> {
+        let mut array = self.0.create_empty_array().map_err(js_error)?;
+
+        let mut index = 0;
+        while let Some(element) = seq.next_element_seed(JsValueSeed(self.0))? {
+            array.set_element(index, element).map_err(js_error)?;
+            index += 1;
+        }
+
+        Ok(array.into_unknown())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<JsUnknown, A::Error> {
+        let mut object = self.0.create_object().map_err(js_error)?;
+
+        while let Some(key) = map.next_key::<String>()? {
+            let key = self.0.create_string(&key).map_err(js_error)?;
+            let value = map.next_value_seed(JsValueSeed(self.0))?;
+            object.set_property(key, value).map_err(js_error)?;
+        }
+
+        Ok(object.into_unknown())
+    }
+}
+
+#[napi]
+pub struct JsonDecompressor {
+    inner: TextDecompressor,
+}
+
+#[napi]
+impl JsonDecompressor {
+    /// Creates a zlib decompressor that parses each inflated message as JSON, taking the same arguments as
+    /// `ZlibDecompressor`.
+    #[napi(constructor)]
+    pub fn new(
+        options: Option<Either<u32, DecompressorOptions>>,
+        window_bits: Option<i32>,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: TextDecompressor::new(options, window_bits)?,
+        })
+    }
+
+    /// Like `TextDecompressor.push`, but also parses the message as JSON, saving the `JSON.parse` call on the JS side.
+    ///
+    /// A message that isn't valid UTF-8 or JSON fails with `Invalid UTF-8` or `Invalid JSON` and is dropped, while the
+    /// stream itself carries on with the next one.
+    #[napi]
+    pub fn push(&mut self, env: Env, data: Uint8Array) -> Result<JsonPushResult> {
+        let result = self.inner.push(data)?;
+
+        let Some(text) = result.text else {
+            return Ok(JsonPushResult {
+                ok: result.ok,
+                value: None,
+                finished: result.finished,
+                error: result.error,
+            });
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_str(&text);
+        let parsed = JsValueSeed(&env)
+            .deserialize(&mut deserializer)
+            .and_then(|value| deserializer.end().map(|_| value));
+
+        Ok(match parsed {
+            Ok(value) => JsonPushResult {
+                ok: true,
+                value: Some(value),
+                finished: result.finished,
+                error: None,
+            },
+            Err(err) => JsonPushResult {
+                ok: false,
+                value: None,
+                finished: result.finished,
+                error: Some(format!("Invalid JSON: {}", err)),
+            },
+        })
+    }
+
+    /// Tears down the underlying `ZlibDecompressor`, see `ZlibDecompressor.dispose`.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.inner.dispose();
+    }
+}
@@ -1,4 +1,84 @@
-mod zlib;
+// The Node-API bindings only exist outside of WebAssembly, where the `wasm` module takes over instead. napi-rs registers
+// its classes through Node-API imports that a wasm32 module has no way to satisfy, so it isn't compiled in at all there.
+#[cfg(all(target_family = "wasm", not(feature = "wasm")))]
+compile_error!("WebAssembly builds need the `wasm` feature");
 
+#[cfg(all(not(target_family = "wasm"), feature = "brotli"))]
+pub mod brotli;
+#[cfg(not(target_family = "wasm"))]
+pub mod deflate;
+#[cfg(not(target_family = "wasm"))]
+pub mod format;
+#[cfg(not(target_family = "wasm"))]
+pub mod http;
+pub mod inflater;
+#[cfg(all(not(target_family = "wasm"), feature = "json"))]
+pub mod json;
+#[cfg(all(not(target_family = "wasm"), feature = "lz4"))]
+pub mod lz4;
+#[cfg(not(target_family = "wasm"))]
+pub mod multi_format;
+#[cfg(not(target_family = "wasm"))]
+pub mod oneshot;
+#[cfg(not(target_family = "wasm"))]
+pub mod options;
+#[cfg(not(target_family = "wasm"))]
+pub mod result;
+#[cfg(all(not(target_family = "wasm"), feature = "snappy"))]
+pub mod snappy;
+#[cfg(not(target_family = "wasm"))]
+pub mod text;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(not(target_family = "wasm"))]
+pub mod zlib;
+#[cfg(all(not(target_family = "wasm"), feature = "zstd"))]
+pub mod zstd;
+
+#[cfg(not(target_family = "wasm"))]
 #[macro_use]
 extern crate napi_derive;
+
+#[cfg(not(target_family = "wasm"))]
+use napi::{Env, JsObject, JsString, JsUnknown, Result, ValueType};
+
+/// Exposes the `dispose` method of every exported class as `[Symbol.dispose]` too, so instances can be declared with
+/// `using` and get cleaned up at the end of their scope.
+///
+/// napi-rs can't name methods after symbols, so the prototypes are patched once all classes are registered. Runtimes
+/// without `Symbol.dispose` are left alone.
+#[cfg(not(target_family = "wasm"))]
+#[module_exports]
+fn init(exports: JsObject, env: Env) -> Result<()> {
+    // Symbol is a function, which get_named_property would refuse to hand out as an object
+    let symbol: JsObject = env.get_global()?.get_named_property_unchecked("Symbol")?;
+    let dispose_symbol: JsUnknown = symbol.get_named_property("dispose")?;
+    if dispose_symbol.get_type()? != ValueType::Symbol {
+        return Ok(());
+    }
+
+    let names = exports.get_property_names()?;
+    for index in 0..names.get_array_length()? {
+        let name: JsString = names.get_element(index)?;
+        let export: JsUnknown = exports.get_property(name)?;
+        if export.get_type()? != ValueType::Function {
+            continue;
+        }
+
+        // SAFETY: Functions are objects, and the type was checked above
+        let class = unsafe { export.cast::<JsObject>() };
+        let prototype: JsUnknown = class.get_named_property("prototype")?;
+        if prototype.get_type()? != ValueType::Object {
+            continue;
+        }
+
+        // SAFETY: The type was checked above
+        let mut prototype = unsafe { prototype.cast::<JsObject>() };
+        let dispose: JsUnknown = prototype.get_named_property("dispose")?;
+        if dispose.get_type()? == ValueType::Function {
+            prototype.set_property(&dispose_symbol, dispose)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use crate::result::{FinishResult, PushResult, DECOMPRESSOR_DISPOSED};
+use ::lz4_flex::frame::FrameDecoder;
+use ::twox_hash::XxHash32;
+use napi::bindgen_prelude::{Result, Uint8Array};
+use std::io::Read;
+
+const LZ4_FRAME_MAGIC: u32 = 0x184D2204;
+const LZ4_SKIPPABLE_MAGIC: std::ops::RangeInclusive<u32> = 0x184D2A50..=0x184D2A5F;
+
+const FLG_BLOCK_CHECKSUMS: u8 = 0b0001_0000;
+const FLG_CONTENT_SIZE: u8 = 0b0000_1000;
+const FLG_CONTENT_CHECKSUM: u8 = 0b0000_0100;
+const FLG_DICTIONARY_ID: u8 = 0b0000_0001;
+const BLOCK_UNCOMPRESSED_BIT: u32 = 0x8000_0000;
+
+/// How large a `Lz4Format.Block` block may say it decompresses to unless told otherwise.
+const DEFAULT_MAX_BLOCK_SIZE: u32 = 64 * 1024 * 1024;
+
+#[napi]
+pub enum Lz4Format {
+    /// A single block with its uncompressed size prepended, as produced by `lz4_flex::compress_prepend_size`
+    Block,
+    /// The standard LZ4 frame format, which may contain several back-to-back frames
+    Frame,
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// The largest block a frame may contain, going by the block maximum size in its BD byte.
+fn frame_max_block_size(bd: u8) -> std::result::Result<u32, String> {
+    match (bd >> 4) & 0b111 {
+        4 => Ok(64 * 1024),
+        5 => Ok(256 * 1024),
+        6 => Ok(1024 * 1024),
+        7 => Ok(4 * 1024 * 1024),
+        other => Err(format!("Lz4 error: invalid block maximum size {}", other)),
+    }
+}
+
+/// The length of a frame header with the given FLG byte: the magic number, FLG, BD and the header checksum, plus the
+/// optional content size and dictionary id.
+fn frame_header_len(flags: u8) -> usize {
+    let mut len = 7;
+    if flags & FLG_CONTENT_SIZE != 0 {
+        len += 8;
+    }
+    if flags & FLG_DICTIONARY_ID != 0 {
+        len += 4;
+    }
+
+    len
+}
+
+/// Walks the frame at the start of `data`, returning its length once all of it is available, or `None` while it is
+/// still incomplete.
+fn complete_frame_len(data: &[u8]) -> std::result::Result<Option<usize>, String> {
+    let Some(magic) = read_u32_le(data, 0) else {
+        return Ok(None);
+    };
+
+    if LZ4_SKIPPABLE_MAGIC.contains(&magic) {
+        return Ok(read_u32_le(data, 4)
+            .map(|user_data_len| 8 + user_data_len as usize)
+            .filter(|&frame_len| frame_len <= data.len()));
+    }
+
+    if magic != LZ4_FRAME_MAGIC {
+        return Err(format!(
+            "Lz4 error: unsupported magic number {:#010x}",
+            magic
+        ));
+    }
+
+    let (Some(&flags), Some(&bd)) = (data.get(4), data.get(5)) else {
+        return Ok(None);
+    };
+    let max_block_size = frame_max_block_size(bd)?;
+
+    let mut offset = frame_header_len(flags);
+
+    loop {
+        let Some(block_info) = read_u32_le(data, offset) else {
+            return Ok(None);
+        };
+        offset += 4;
+
+        // A zero-sized block is the end mark
+        if block_info == 0 {
+            break;
+        }
+
+        // Checked before waiting for the rest of the block, so a bogus size can't make the whole thing get buffered
+        let block_size = block_info & !BLOCK_UNCOMPRESSED_BIT;
+        if block_size > max_block_size {
+            return Err(format!(
+                "Lz4 error: block of {} bytes is larger than the frame's maximum of {}",
+                block_size, max_block_size
+            ));
+        }
+
+        offset += block_size as usize;
+        if flags & FLG_BLOCK_CHECKSUMS != 0 {
+            offset += 4;
+        }
+    }
+
+    if flags & FLG_CONTENT_CHECKSUM != 0 {
+        offset += 4;
+    }
+
+    Ok((offset <= data.len()).then_some(offset))
+}
+
+/// Overwrites every block checksum in `frame` that doesn't match its block with the right one, so the frame decoder
+/// goes on to decode those blocks instead of failing, returning how many there were. `frame` must be complete, as
+/// vouched for by `complete_frame_len`.
+fn repair_block_checksums(frame: &mut [u8]) -> u32 {
+    if read_u32_le(frame, 0) != Some(LZ4_FRAME_MAGIC) || frame[4] & FLG_BLOCK_CHECKSUMS == 0 {
+        return 0;
+    }
+
+    let mut offset = frame_header_len(frame[4]);
+    let mut mismatches = 0;
+
+    loop {
+        let block_info = read_u32_le(frame, offset).unwrap_or(0);
+        offset += 4;
+
+        if block_info == 0 {
+            return mismatches;
+        }
+
+        // The checksum covers the block as stored, compressed or not
+        let block_end = offset + (block_info & !BLOCK_UNCOMPRESSED_BIT) as usize;
+        let expected = XxHash32::oneshot(0, &frame[offset..block_end]);
+
+        let checksum = &mut frame[block_end..block_end + 4];
+        if checksum != expected.to_le_bytes() {
+            checksum.copy_from_slice(&expected.to_le_bytes());
+            mismatches += 1;
+        }
+
+        offset = block_end + 4;
+    }
+}
+
+#[napi]
+pub struct Lz4Decompressor {
+    format: Lz4Format,
+    // Largest uncompressed size a Lz4Format.Block block may declare
+    max_block_size: u32,
+    // Input that doesn't make up a complete frame (or block) yet
+    internal_buffer: Vec<u8>,
+    // Track finished state separately (for terminal errors or the end of the stream)
+    finished: bool,
+    // Set once dispose has dropped the buffered input
+    disposed: bool,
+    // Frame blocks decoded despite a block checksum that didn't match
+    block_checksum_errors: u32,
+}
+
+#[napi]
+impl Lz4Decompressor {
+    /// Creates a decompressor for the given format.
+    ///
+    /// `maxBlockSize` caps the uncompressed size a `Lz4Format.Block` block may declare, defaulting to 64 MiB, since
+    /// that much is allocated up front when it is decoded. Frames are only held to the maximum block size in their
+    /// header.
+    #[napi(constructor)]
+    pub fn new(format: Lz4Format, max_block_size: Option<u32>) -> Self {
+        Self {
+            format,
+            max_block_size: max_block_size.unwrap_or(DEFAULT_MAX_BLOCK_SIZE),
+            internal_buffer: Vec::new(),
+            finished: false,
+            disposed: false,
+            block_checksum_errors: 0,
+        }
+    }
+
+    /// Decompresses every frame of the input that has fully arrived.
+    ///
+    /// LZ4 frames are decoded whole, so input is buffered until the end of a frame is available. `bytesConsumed` is
+    /// how many buffered bytes were decoded during this call. Blocks larger than the maximum declared in their
+    /// frame's header fail as soon as their size arrives. Blocks whose block checksum doesn't match are decoded anyway
+    /// and counted in `blockChecksumErrors`.
+    ///
+    /// In `Lz4Format.Block` mode the block's compressed length isn't known up front, so everything is buffered and
+    /// only decoded by `finish`. Blocks declaring more than `maxBlockSize` bytes fail right away, as does input that
+    /// grows past the largest a block of the declared size can compress to.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            // Already finished (due to error or end of stream), return early
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        self.internal_buffer.extend_from_slice(&data);
+
+        if matches!(self.format, Lz4Format::Block) {
+            if let Err(message) = self.check_block() {
+                self.finished = true;
+                self.internal_buffer = Vec::new();
+                return Ok(PushResult::error(message));
+            }
+
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        let mut output_buffer = Vec::new();
+        let mut bytes_consumed = 0;
+
+        loop {
+            let frame_len = match complete_frame_len(&self.internal_buffer[bytes_consumed..]) {
+                Ok(Some(frame_len)) => frame_len,
+                Ok(None) => break,
+                Err(message) => {
+                    self.finished = true;
+                    return Ok(PushResult::error(message));
+                }
+            };
+
+            let frame = &mut self.internal_buffer[bytes_consumed..bytes_consumed + frame_len];
+            self.block_checksum_errors += repair_block_checksums(frame);
+
+            if let Err(err) = FrameDecoder::new(&*frame).read_to_end(&mut output_buffer) {
+                self.finished = true;
+                return Ok(PushResult::error(format!("Lz4 error: {}", err)));
+            }
+
+            bytes_consumed += frame_len;
+        }
+
+        self.internal_buffer.drain(..bytes_consumed);
+
+        Ok(PushResult::ok(bytes_consumed, output_buffer))
+    }
+
+    /// How many frame blocks were decoded despite a block checksum that didn't match them, so callers can decide how
+    /// much corruption to put up with.
+    ///
+    /// Such blocks are still decoded, so corrupted data may make decoding fail or come out wrong. If the frame has a
+    /// content checksum, it still fails the frame when the output is wrong.
+    #[napi(getter)]
+    pub fn block_checksum_errors(&self) -> u32 {
+        self.block_checksum_errors
+    }
+
+    /// Checks that the stream did not end in the middle of a frame.
+    ///
+    /// In `Lz4Format.Block` mode this is where the buffered block gets decoded, so `data` holds the whole output.
+    /// In `Lz4Format.Frame` mode all output is already handed out by `push`, so `data` is always `null`.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        if self.disposed {
+            return Ok(FinishResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            return Ok(FinishResult::ok(Vec::new()));
+        }
+
+        self.finished = true;
+
+        if matches!(self.format, Lz4Format::Block) {
+            // The declared size is allocated up front, so it's checked right before that happens too
+            if let Err(message) = self.check_block() {
+                self.internal_buffer = Vec::new();
+                return Ok(FinishResult::error(message));
+            }
+        }
+
+        let pending = std::mem::take(&mut self.internal_buffer);
+
+        match self.format {
+            Lz4Format::Block => match ::lz4_flex::decompress_size_prepended(&pending) {
+                Ok(output) => Ok(FinishResult::ok(output)),
+                Err(err) => Ok(FinishResult::error(format!("Lz4 error: {}", err))),
+            },
+            Lz4Format::Frame if !pending.is_empty() => {
+                Ok(FinishResult::error("Unexpected end of lz4 stream"))
+            }
+            Lz4Format::Frame => Ok(FinishResult::ok(Vec::new())),
+        }
+    }
+
+    /// Drops the buffered input right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.internal_buffer = Vec::new();
+        self.finished = true;
+        self.disposed = true;
+    }
+}
+
+impl Lz4Decompressor {
+    /// Checks the buffered `Lz4Format.Block` input against the size the block declares, once that has arrived.
+    fn check_block(&self) -> std::result::Result<(), String> {
+        let Some(declared_size) = read_u32_le(&self.internal_buffer, 0) else {
+            return Ok(());
+        };
+
+        if declared_size > self.max_block_size {
+            return Err(format!(
+                "Lz4 error: block of {} bytes is larger than the maximum of {}",
+                declared_size, self.max_block_size
+            ));
+        }
+
+        // The size prefix, plus the most a block of that size can compress to
+        let max_len = 4 + ::lz4_flex::block::get_maximum_output_size(declared_size as usize);
+        if self.internal_buffer.len() > max_len {
+            return Err(format!(
+                "Lz4 error: block is longer than {} bytes could compress to",
+                declared_size
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::format::{gzip_header_len, zlib_header_window_bits, CompressionFormat};
+use crate::oneshot::{inflate_frames, window_bits_for};
+use napi::bindgen_prelude::{Buffer, Result, Status};
+use napi::Error;
+use zlib_rs::inflate::InflateConfig;
+
+#[napi]
+pub struct MultiFormatDecompressor {
+    // The format the last successful decompress call detected
+    last_format: Option<CompressionFormat>,
+}
+
+impl Default for MultiFormatDecompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[napi]
+impl MultiFormatDecompressor {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self { last_format: None }
+    }
+
+    /// Decompresses `data` without knowing its format up front, for responses that come without a
+    /// `Content-Encoding` header.
+    ///
+    /// gzip is tried first if `data` starts with a valid gzip header, then zlib if it starts with a valid zlib header,
+    /// and finally raw deflate. Each attempt uses a fresh inflate stream, and the output of the first one that
+    /// decompresses all of `data` is returned. Back-to-back streams (such as gzip members) are concatenated.
+    #[napi]
+    pub fn decompress(&mut self, data: Buffer) -> Result<Buffer> {
+        let candidates = [
+            (CompressionFormat::Gzip, gzip_header_len(&data).is_some()),
+            (
+                CompressionFormat::Zlib,
+                zlib_header_window_bits(&data).is_some(),
+            ),
+            (CompressionFormat::Deflate, true),
+        ];
+
+        let mut failures = Vec::new();
+
+        for (format, plausible) in candidates {
+            if !plausible {
+                continue;
+            }
+
+            let config = InflateConfig {
+                window_bits: window_bits_for(format)?,
+            };
+
+            match inflate_frames(&data, config) {
+                Ok(frames) => {
+                    self.last_format = Some(format);
+                    return Ok(frames.concat().into());
+                }
+                Err(ret_code) => failures.push(format!("{:?}: {:?}", format, ret_code)),
+            }
+        }
+
+        Err(Error::new(
+            Status::GenericFailure,
+            format!(
+                "Data is not valid gzip, zlib or raw deflate ({})",
+                failures.join(", ")
+            ),
+        ))
+    }
+
+    /// The format the last successful `decompress` call detected, or `null` if there hasn't been one yet.
+    #[napi(getter)]
+    pub fn last_format(&self) -> Option<CompressionFormat> {
+        self.last_format
+    }
+}
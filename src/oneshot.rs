@@ -0,0 +1,587 @@
+use crate::format::{zlib_header_window_bits, CompressionFormat};
+use napi::bindgen_prelude::{AsyncTask, Buffer, Env, Result, Status, Task};
+use napi::Error;
+use rayon::prelude::*;
+use std::time::{Duration, Instant};
+use zlib_rs::{
+    c_api::z_stream,
+    deflate::{self, DeflateConfig, DeflateStream, Strategy},
+    inflate::{self, InflateConfig, InflateStream},
+    DeflateFlush, InflateFlush, ReturnCode,
+};
+
+const OUTPUT_CHUNK_SIZE: usize = 16 * 1024;
+const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Initializes an inflate stream with `config`, hands it to `f` and tears it down again afterwards.
+fn with_inflate_stream<T>(
+    config: InflateConfig,
+    f: impl FnOnce(&mut z_stream) -> std::result::Result<T, ReturnCode>,
+) -> std::result::Result<T, ReturnCode> {
+    let mut stream = z_stream::default();
+
+    let ret_code = inflate::init(&mut stream, config);
+    if ret_code != ReturnCode::Ok {
+        return Err(ret_code);
+    }
+
+    let result = f(&mut stream);
+
+    // SAFETY: The stream is still initialized at this point, so it is safe to tear it down
+    if let Some(inflate_stream_ref) = unsafe { InflateStream::from_stream_mut(&mut stream) } {
+        inflate::end(inflate_stream_ref);
+    }
+
+    result
+}
+
+/// Inflates `data` until the end of the stream, returning the output and how many bytes of `data` were consumed.
+fn inflate_frame(
+    stream: &mut z_stream,
+    data: &[u8],
+) -> std::result::Result<(Vec<u8>, usize), ReturnCode> {
+    stream.next_in = data.as_ptr() as *mut u8;
+    stream.avail_in = data.len().try_into().map_err(|_| ReturnCode::BufError)?;
+
+    let mut output_buffer = Vec::new();
+    let mut temp_out_buf = vec![0u8; OUTPUT_CHUNK_SIZE];
+
+    loop {
+        stream.next_out = temp_out_buf.as_mut_ptr();
+        stream.avail_out = OUTPUT_CHUNK_SIZE as u32;
+
+        // SAFETY: The stream was initialized by the caller and our pointers are all valid
+        let result_code = match unsafe { InflateStream::from_stream_mut(stream) } {
+            Some(inflate_stream_ref) => unsafe {
+                inflate::inflate(inflate_stream_ref, InflateFlush::NoFlush)
+            },
+            None => return Err(ReturnCode::StreamError),
+        };
+
+        let written_in_call = OUTPUT_CHUNK_SIZE - stream.avail_out as usize;
+        output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+
+        match result_code {
+            ReturnCode::StreamEnd => {
+                return Ok((output_buffer, data.len() - stream.avail_in as usize));
+            }
+            ReturnCode::Ok => continue,
+            // No progress possible, which means the input ended before the stream did
+            other_code => return Err(other_code),
+        }
+    }
+}
+
+/// Inflates a complete zlib stream in one go, returning the offending return code on failure.
+pub(crate) fn inflate_all(data: &[u8]) -> std::result::Result<Vec<u8>, ReturnCode> {
+    with_inflate_stream(InflateConfig::default(), |stream| {
+        inflate_frame(stream, data).map(|(output, _)| output)
+    })
+}
+
+/// Inflates back-to-back streams (such as the members of a multi-member gzip file), one output per stream.
+pub(crate) fn inflate_frames(
+    data: &[u8],
+    config: InflateConfig,
+) -> std::result::Result<Vec<Vec<u8>>, ReturnCode> {
+    with_inflate_stream(config, |stream| {
+        let mut frames = Vec::new();
+        let mut remaining = data;
+
+        loop {
+            let (output, consumed) = inflate_frame(stream, remaining)?;
+            frames.push(output);
+            remaining = &remaining[consumed..];
+
+            if remaining.is_empty() {
+                return Ok(frames);
+            }
+
+            // SAFETY: The stream is initialized, so it can be reset for the next frame
+            match unsafe { InflateStream::from_stream_mut(stream) } {
+                Some(inflate_stream_ref) => inflate::reset(inflate_stream_ref),
+                None => return Err(ReturnCode::StreamError),
+            };
+        }
+    })
+}
+
+/// Deflates `data` in one go with the given configuration, returning the offending return code on failure.
+pub(crate) fn deflate_all(
+    data: &[u8],
+    config: DeflateConfig,
+) -> std::result::Result<Vec<u8>, ReturnCode> {
+    // Checked before init, so there's no initialized stream to tear down when the input is too large
+    let avail_in = data.len().try_into().map_err(|_| ReturnCode::BufError)?;
+
+    let mut stream = z_stream::default();
+
+    let ret_code = deflate::init(&mut stream, config);
+    if ret_code != ReturnCode::Ok {
+        return Err(ret_code);
+    }
+
+    stream.next_in = data.as_ptr() as *mut u8;
+    stream.avail_in = avail_in;
+
+    let mut output_buffer = Vec::new();
+    let mut temp_out_buf = vec![0u8; OUTPUT_CHUNK_SIZE];
+
+    let result = loop {
+        stream.next_out = temp_out_buf.as_mut_ptr();
+        stream.avail_out = OUTPUT_CHUNK_SIZE as u32;
+
+        // SAFETY: The stream was initialized above and our pointers are all valid
+        let result_code = match unsafe { DeflateStream::from_stream_mut(&mut stream) } {
+            Some(deflate_stream_ref) => deflate::deflate(deflate_stream_ref, DeflateFlush::Finish),
+            None => break Err(ReturnCode::StreamError),
+        };
+
+        let written_in_call = OUTPUT_CHUNK_SIZE - stream.avail_out as usize;
+        output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+
+        match result_code {
+            ReturnCode::StreamEnd => break Ok(output_buffer),
+            // Output buffer was filled before everything could be flushed, go around again
+            ReturnCode::Ok => continue,
+            other_code => break Err(other_code),
+        }
+    };
+
+    // SAFETY: The stream is still initialized at this point, so it is safe to tear it down
+    if let Some(deflate_stream_ref) = unsafe { DeflateStream::from_stream_mut(&mut stream) } {
+        let _ = deflate::end(deflate_stream_ref);
+    }
+
+    result
+}
+
+/// Resolves an optional user-provided level, rejecting anything outside of zlib's 0-9 range.
+pub(crate) fn resolve_level(level: Option<u32>) -> Result<i32> {
+    validate_level(level.unwrap_or(DEFAULT_COMPRESSION_LEVEL))
+}
+
+/// Rejects any level outside of zlib's 0-9 range.
+pub(crate) fn validate_level(level: impl Into<i64>) -> Result<i32> {
+    match level.into() {
+        level @ 0..=9 => Ok(level as i32),
+        level => Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid compression level {}, expected 0-9", level),
+        )),
+    }
+}
+
+/// Raw deflate with Huffman coding only, as used by `deflate_huffman_only_block` and its batch version.
+fn huffman_only_config() -> DeflateConfig {
+    DeflateConfig {
+        window_bits: -15,
+        strategy: Strategy::HuffmanOnly,
+        ..DeflateConfig::default()
+    }
+}
+
+/// Maps the zlib-family formats to the window bits selecting their framing.
+pub(crate) fn window_bits_for(format: CompressionFormat) -> Result<i32> {
+    match format {
+        CompressionFormat::Zlib => Ok(15),
+        CompressionFormat::Gzip => Ok(31),
+        CompressionFormat::Deflate => Ok(-15),
+        other => Err(Error::new(
+            Status::InvalidArg,
+            format!("Unsupported compression format {:?}", other),
+        )),
+    }
+}
+
+/// Builds the deflate configuration producing `format`, for the zlib-family formats.
+pub(crate) fn deflate_config_for(format: CompressionFormat, level: i32) -> Result<DeflateConfig> {
+    Ok(DeflateConfig {
+        window_bits: window_bits_for(format)?,
+        ..DeflateConfig::new(validate_level(level)?)
+    })
+}
+
+fn inflate_error(ret_code: ReturnCode) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Inflate error: {:?}", ret_code),
+    )
+}
+
+pub(crate) fn deflate_error(ret_code: ReturnCode) -> Error {
+    Error::new(
+        Status::GenericFailure,
+        format!("Deflate error: {:?}", ret_code),
+    )
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn invalid_hex_char(position: usize) -> Error {
+    Error::new(
+        Status::InvalidArg,
+        format!(
+            "Invalid hex string: non-hex character at position {}",
+            position
+        ),
+    )
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid hex string: odd length ({})", hex.len()),
+        ));
+    }
+
+    hex.as_bytes()
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(index, pair)| {
+            let high = hex_value(pair[0]).ok_or_else(|| invalid_hex_char(index * 2))?;
+            let low = hex_value(pair[1]).ok_or_else(|| invalid_hex_char(index * 2 + 1))?;
+
+            Ok((high << 4) | low)
+        })
+        .collect()
+}
+
+/// Hex-decodes `hex` and inflates the resulting zlib stream.
+///
+/// Invalid hex input is reported with an `InvalidArg` status, while zlib failures use `GenericFailure`.
+#[napi]
+pub fn decompress_hex_sync(hex: String) -> Result<Buffer> {
+    let compressed = decode_hex(&hex)?;
+
+    inflate_all(&compressed)
+        .map(Buffer::from)
+        .map_err(inflate_error)
+}
+
+/// Compresses `data` into a zlib-wrapped deflate stream (with an Adler-32 trailer).
+///
+/// `level` defaults to 6.
+#[napi]
+pub fn compress_deflate(data: Buffer, level: Option<u32>) -> Result<Buffer> {
+    let config = DeflateConfig::new(resolve_level(level)?);
+
+    let compressed = deflate_all(&data, config).map_err(deflate_error)?;
+
+    if zlib_header_window_bits(&compressed).is_none() {
+        return Err(Error::new(
+            Status::GenericFailure,
+            "Deflate produced output without a valid zlib header",
+        ));
+    }
+
+    Ok(compressed.into())
+}
+
+/// Compresses `data` on its own using Huffman coding only, skipping LZ77 match finding (`Z_HUFFMAN_ONLY`).
+///
+/// This is cheaper than regular deflate for data that has already been de-duplicated. The result is raw deflate data,
+/// block headers included but without the zlib header and trailer, with the last block marked as final.
+///
+/// Deflate ends a block whenever its buffer of 16K symbols fills up, which takes one symbol per byte here, so only
+/// inputs of up to 16383 bytes come out as a single block. Larger ones come out as several blocks, each with its own
+/// Huffman codes, of which only the last is marked as final, so the result is still one raw deflate stream.
+#[napi]
+pub fn deflate_huffman_only_block(data: Buffer) -> Result<Buffer> {
+    deflate_all(&data, huffman_only_config())
+        .map(Buffer::from)
+        .map_err(deflate_error)
+}
+
+/// Batch version of `deflateHuffmanOnlyBlock`, compressing every buffer on its own, in parallel.
+///
+/// The result holds the raw deflate data of every buffer back to back, in order. Each one ends with a final block, so
+/// `decompressAllFrames(result, CompressionFormat.Deflate)` splits them apart again.
+#[napi]
+pub fn huffman_only_compress_all(data: Vec<Buffer>) -> Result<Buffer> {
+    let chunks: Vec<&[u8]> = data.iter().map(|chunk| chunk.as_ref()).collect();
+
+    let config = huffman_only_config();
+
+    let compressed = chunks
+        .par_iter()
+        .map(|chunk| deflate_all(chunk, config).map_err(deflate_error))
+        .collect::<Result<Vec<_>>>()?;
+
+    let capacity = chunks
+        .iter()
+        .map(|chunk| deflate::bound(None, chunk.len()))
+        .sum();
+
+    let mut output_buffer = Vec::with_capacity(capacity);
+    for chunk in compressed {
+        output_buffer.extend_from_slice(&chunk);
+    }
+
+    Ok(output_buffer.into())
+}
+
+/// Compresses `data` into a zlib-wrapped deflate stream at increasing levels, starting from 1, until the output is
+/// at least `target_ratio` smaller than the input (`1 - compressed / input >= target_ratio`) or `max_time_ms`
+/// milliseconds have passed.
+///
+/// Returns the output of the last level that was tried. Level 1 always runs to completion, even if it takes longer
+/// than `max_time_ms`.
+#[napi]
+pub fn compress_level_auto(data: Buffer, target_ratio: f64, max_time_ms: u32) -> Result<Buffer> {
+    if !(0.0..=1.0).contains(&target_ratio) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid target ratio {}, expected 0-1", target_ratio),
+        ));
+    }
+
+    let started = Instant::now();
+    let time_budget = Duration::from_millis(u64::from(max_time_ms));
+
+    let mut compressed = Vec::new();
+
+    for level in 1..=9 {
+        compressed = deflate_all(&data, DeflateConfig::new(level)).map_err(deflate_error)?;
+
+        let savings = 1.0 - compressed.len() as f64 / data.len().max(1) as f64;
+        if savings >= target_ratio || started.elapsed() >= time_budget {
+            break;
+        }
+    }
+
+    Ok(compressed.into())
+}
+
+pub struct CompressBatchTask {
+    items: Vec<Vec<u8>>,
+    config: DeflateConfig,
+}
+
+impl Task for CompressBatchTask {
+    type Output = Vec<Vec<u8>>;
+    type JsValue = Vec<Buffer>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let config = self.config;
+
+        self.items
+            .par_iter()
+            .map(|item| deflate_all(item, config).map_err(deflate_error))
+            .collect()
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into_iter().map(Buffer::from).collect())
+    }
+}
+
+/// Compresses every item independently off the main thread, spreading the work across a thread pool.
+///
+/// The resolved array preserves the order of `items`. Only the zlib-family formats (gzip, zlib and raw deflate)
+/// are supported.
+#[napi]
+pub fn compress_batch_async(
+    items: Vec<Buffer>,
+    format: CompressionFormat,
+    level: i32,
+) -> Result<AsyncTask<CompressBatchTask>> {
+    let config = deflate_config_for(format, level)?;
+
+    Ok(AsyncTask::new(CompressBatchTask {
+        items: items.iter().map(|item| item.to_vec()).collect(),
+        config,
+    }))
+}
+
+/// Decompresses every back-to-back stream in `data` separately, such as the members of a multi-member gzip file.
+///
+/// Only the zlib-family formats (gzip, zlib and raw deflate) are supported.
+#[napi]
+pub fn decompress_all_frames(data: Buffer, format: CompressionFormat) -> Result<Vec<Buffer>> {
+    let config = InflateConfig {
+        window_bits: window_bits_for(format)?,
+    };
+
+    let frames = inflate_frames(&data, config).map_err(inflate_error)?;
+
+    Ok(frames.into_iter().map(Buffer::from).collect())
+}
+
+pub struct DecompressAllTask {
+    data: Vec<u8>,
+}
+
+impl Task for DecompressAllTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        inflate_all(&self.data).map_err(inflate_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+/// Inflates a complete zlib stream off the main thread, so large inputs don't block the event loop.
+///
+/// `data` is copied before the task is queued, so the caller's buffer can be reused right away.
+#[napi]
+pub fn decompress_all_async(data: Buffer) -> AsyncTask<DecompressAllTask> {
+    AsyncTask::new(DecompressAllTask {
+        data: data.to_vec(),
+    })
+}
+
+pub struct CompressAllTask {
+    data: Vec<u8>,
+    config: DeflateConfig,
+}
+
+impl Task for CompressAllTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        deflate_all(&self.data, self.config).map_err(deflate_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+/// Async version of `compressDeflate`, deflating `data` into a zlib-wrapped stream off the main thread.
+///
+/// `level` defaults to 6 and is validated right away, so an invalid level throws instead of rejecting. `data` is
+/// copied before the task is queued.
+#[napi]
+pub fn compress_all_async(data: Buffer, level: Option<u32>) -> Result<AsyncTask<CompressAllTask>> {
+    let config = DeflateConfig::new(resolve_level(level)?);
+
+    Ok(AsyncTask::new(CompressAllTask {
+        data: data.to_vec(),
+        config,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // AsyncTask doesn't hand its task back out, so the tasks are built like the `*_async` functions build them and
+    // run with compute, which is what the thread pool calls
+
+    #[test]
+    fn compress_batch_task_compresses_every_item() {
+        let items = vec![
+            b"first item".to_vec(),
+            Vec::new(),
+            b"third item".repeat(100),
+        ];
+
+        let mut task = CompressBatchTask {
+            items: items.clone(),
+            config: deflate_config_for(CompressionFormat::Gzip, 6).unwrap(),
+        };
+
+        let compressed = task.compute().unwrap();
+        assert_eq!(compressed.len(), items.len());
+
+        let config = InflateConfig {
+            window_bits: window_bits_for(CompressionFormat::Gzip).unwrap(),
+        };
+        for (compressed, item) in compressed.iter().zip(&items) {
+            assert_eq!(
+                inflate_frames(compressed, config).ok(),
+                Some(vec![item.clone()])
+            );
+        }
+    }
+
+    #[test]
+    fn compress_all_task_round_trips_through_decompress_all_task() {
+        let data = b"compress me off the main thread, ".repeat(1000);
+
+        let mut compress = CompressAllTask {
+            data: data.clone(),
+            config: DeflateConfig::new(resolve_level(Some(9)).unwrap()),
+        };
+        let compressed = compress.compute().unwrap();
+        assert!(compressed.len() < data.len());
+
+        let mut decompress = DecompressAllTask { data: compressed };
+        assert_eq!(decompress.compute().unwrap(), data);
+    }
+
+    /// Counts the blocks of the raw deflate stream `data`, by having inflate stop after every one of them.
+    fn count_blocks(data: &[u8]) -> usize {
+        let config = InflateConfig { window_bits: -15 };
+
+        with_inflate_stream(config, |stream| {
+            stream.next_in = data.as_ptr() as *mut u8;
+            stream.avail_in = data.len() as u32;
+
+            let mut output = vec![0u8; 1024 * 1024];
+            stream.next_out = output.as_mut_ptr();
+            stream.avail_out = output.len() as u32;
+
+            let mut blocks = 0;
+            loop {
+                let total_out = stream.total_out;
+
+                // SAFETY: The stream was initialized by with_inflate_stream and our pointers are all valid
+                let inflate_stream_ref = unsafe { InflateStream::from_stream_mut(stream) }.unwrap();
+                let ret_code = unsafe { inflate::inflate(inflate_stream_ref, InflateFlush::Block) };
+
+                if stream.total_out > total_out {
+                    blocks += 1;
+                }
+
+                match ret_code {
+                    ReturnCode::StreamEnd => return Ok(blocks),
+                    ReturnCode::Ok => {}
+                    ret_code => return Err(ret_code),
+                }
+            }
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn deflate_huffman_only_block_splits_large_inputs_into_blocks() {
+        let data: Vec<u8> = (0..64 * 1024u32).map(|i| (i * 7919 % 251) as u8).collect();
+
+        let single = deflate_huffman_only_block(data[..16383].to_vec().into()).unwrap();
+        assert_eq!(count_blocks(&single), 1);
+
+        let split = deflate_huffman_only_block(data[..16384].to_vec().into()).unwrap();
+        assert_eq!(count_blocks(&split), 2);
+
+        let compressed = deflate_huffman_only_block(data.clone().into()).unwrap();
+        assert_eq!(count_blocks(&compressed), 5);
+
+        // Only the last block is final, so it's all one stream
+        let config = InflateConfig { window_bits: -15 };
+        assert_eq!(inflate_frames(&compressed, config).ok(), Some(vec![data]));
+    }
+
+    #[test]
+    fn decompress_all_task_rejects_malformed_data() {
+        let mut task = DecompressAllTask {
+            data: b"definitely not zlib".to_vec(),
+        };
+
+        assert!(task.compute().is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::deflate::CompressionStrategy;
+use napi::bindgen_prelude::{Buffer, Either};
+
+pub(crate) use crate::inflater::DEFAULT_CHUNK_SIZE;
+
+/// Options for `ZlibDecompressor`, as an alternative to its positional constructor arguments.
+#[napi(object)]
+#[derive(Default)]
+pub struct DecompressorOptions {
+    /// Size of the chunks the output is produced in, defaults to 16 KiB
+    pub chunk_size: Option<u32>,
+    /// The window bits inflate is initialized with, defaults to 15 (zlib-wrapped, 32 KiB window)
+    pub window_bits: Option<i32>,
+    /// The preset dictionary the stream was compressed with, if any
+    pub dictionary: Option<Buffer>,
+    /// How many bytes a single call may produce. Going over it fails with `Output size limit exceeded` and finishes
+    /// the decompressor, as a defense against decompression bombs
+    pub max_output_size: Option<u32>,
+}
+
+impl DecompressorOptions {
+    /// Resolves the constructor's arguments, which are either a chunk size or an options object, followed by the
+    /// window bits. Window bits set in the options object take precedence.
+    pub(crate) fn from_args(
+        options: Option<Either<u32, DecompressorOptions>>,
+        window_bits: Option<i32>,
+    ) -> Self {
+        let mut options = match options {
+            Some(Either::A(chunk_size)) => Self {
+                chunk_size: Some(chunk_size),
+                ..Self::default()
+            },
+            Some(Either::B(options)) => options,
+            None => Self::default(),
+        };
+
+        options.window_bits = options.window_bits.or(window_bits);
+        options
+    }
+}
+
+/// Options for `DeflateCompressor`, as an alternative to its positional constructor arguments.
+#[napi(object)]
+#[derive(Default)]
+pub struct CompressorOptions {
+    /// Size of the chunks the output is produced in, defaults to 16 KiB
+    pub chunk_size: Option<u32>,
+    /// Compression level within 0-9, defaults to 6
+    pub level: Option<u32>,
+    /// The window bits deflate is initialized with, defaults to 15 (zlib-wrapped, 32 KiB window). -15 to -9 produce
+    /// raw deflate and 25-31 gzip-wrapped streams
+    pub window_bits: Option<i32>,
+    /// How deflate looks for matches, defaults to `CompressionStrategy.Default`
+    pub strategy: Option<CompressionStrategy>,
+}
+
+impl CompressorOptions {
+    /// Resolves the constructor's arguments, which are either a chunk size or an options object, followed by the
+    /// level. A level set in the options object takes precedence.
+    pub(crate) fn from_args(
+        options: Option<Either<u32, CompressorOptions>>,
+        level: Option<u32>,
+    ) -> Self {
+        let mut options = match options {
+            Some(Either::A(chunk_size)) => Self {
+                chunk_size: Some(chunk_size),
+                ..Self::default()
+            },
+            Some(Either::B(options)) => options,
+            None => Self::default(),
+        };
+
+        options.level = options.level.or(level);
+        options
+    }
+}
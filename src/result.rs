@@ -0,0 +1,98 @@
+use crate::inflater::{InflaterError, InflaterErrorKind, PushOutcome};
+use napi::bindgen_prelude::{Buffer, Either, Null};
+use napi::{Error, Status};
+
+pub(crate) use crate::inflater::DECOMPRESSOR_DISPOSED;
+/// Error every call into a compressor fails with once it has been disposed.
+pub(crate) const COMPRESSOR_DISPOSED: &str = "Compressor has been disposed";
+
+/// Result of pushing data into a decompressor or compressor.
+///
+/// `data` is always present, and is `null` when nothing was produced. `ok` results also carry `bytesConsumed`, while
+/// failed results carry `error`.
+#[napi(object, object_from_js = false)]
+pub struct PushResult {
+    pub ok: bool,
+    pub bytes_consumed: Option<u32>,
+    pub data: Either<Buffer, Null>,
+    pub error: Option<String>,
+}
+
+impl PushResult {
+    pub(crate) fn ok(bytes_consumed: usize, output: Vec<u8>) -> Self {
+        Self {
+            ok: true,
+            bytes_consumed: Some(bytes_consumed as u32),
+            data: if output.is_empty() {
+                Either::B(Null)
+            } else {
+                Either::A(output.into())
+            },
+            error: None,
+        }
+    }
+
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            bytes_consumed: None,
+            data: Either::B(Null),
+            error: Some(message.into()),
+        }
+    }
+}
+
+impl From<PushOutcome> for PushResult {
+    fn from(outcome: PushOutcome) -> Self {
+        match outcome {
+            PushOutcome::Ok {
+                bytes_consumed,
+                output,
+            } => Self::ok(bytes_consumed, output),
+            PushOutcome::Failed(message) => Self::error(message),
+        }
+    }
+}
+
+impl From<InflaterError> for Error {
+    fn from(err: InflaterError) -> Self {
+        let status = match err.kind {
+            InflaterErrorKind::InvalidArg => Status::InvalidArg,
+            InflaterErrorKind::Failure => Status::GenericFailure,
+        };
+
+        Error::new(status, err.message)
+    }
+}
+
+/// Result of finishing a stream.
+///
+/// `data` holds whatever output was still pending, or `null` if there was none. Failed results carry `error`.
+#[napi(object, object_from_js = false)]
+pub struct FinishResult {
+    pub ok: bool,
+    pub data: Either<Buffer, Null>,
+    pub error: Option<String>,
+}
+
+impl FinishResult {
+    pub(crate) fn ok(output: Vec<u8>) -> Self {
+        Self {
+            ok: true,
+            data: if output.is_empty() {
+                Either::B(Null)
+            } else {
+                Either::A(output.into())
+            },
+            error: None,
+        }
+    }
+
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            data: Either::B(Null),
+            error: Some(message.into()),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::result::{FinishResult, PushResult, COMPRESSOR_DISPOSED, DECOMPRESSOR_DISPOSED};
+use ::snap::raw::{decompress_len, max_compress_len, Decoder, Encoder};
+use napi::bindgen_prelude::{Result, Status, Uint8Array};
+use napi::Error;
+
+/// How large a block may say it decompresses to unless told otherwise.
+const DEFAULT_MAX_BLOCK_SIZE: u32 = 64 * 1024 * 1024;
+
+fn validate_chunk_size(chunk_size: u32) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "Chunk size must be greater than 0",
+        ));
+    }
+
+    Ok(())
+}
+
+#[napi]
+pub struct SnappyDecompressor {
+    decoder: Decoder,
+    // Largest uncompressed size a block may declare
+    max_block_size: u32,
+    // Track finished state separately (for terminal errors)
+    finished: bool,
+    // Set once dispose was called
+    disposed: bool,
+}
+
+impl Default for SnappyDecompressor {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[napi]
+impl SnappyDecompressor {
+    /// Creates a snappy decompressor.
+    ///
+    /// `maxBlockSize` caps the uncompressed size a block may declare in its header, defaulting to 64 MiB, since that
+    /// much is allocated up front when it is decoded.
+    #[napi(constructor)]
+    pub fn new(max_block_size: Option<u32>) -> Self {
+        Self {
+            decoder: Decoder::new(),
+            max_block_size: max_block_size.unwrap_or(DEFAULT_MAX_BLOCK_SIZE),
+            finished: false,
+            disposed: false,
+        }
+    }
+
+    /// Decompresses `data` as one complete raw snappy block.
+    ///
+    /// Snappy is a block format with no framing of its own, so every `push` must hold exactly one whole block, and
+    /// its output is returned right away. `bytesConsumed` is always the length of `data`. Blocks declaring more than
+    /// `maxBlockSize` bytes fail before anything is allocated for them.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if self.finished {
+            // Already finished (due to an error), return early
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        // The output buffer is sized from the header, which can claim up to 4 GiB in a handful of bytes. A header that
+        // can't be read is left for the decoder to report
+        if let Ok(declared_size) = decompress_len(&data) {
+            if declared_size > self.max_block_size as usize {
+                self.finished = true;
+                return Ok(PushResult::error(format!(
+                    "Snappy error: block of {} bytes is larger than the maximum of {}",
+                    declared_size, self.max_block_size
+                )));
+            }
+        }
+
+        match self.decoder.decompress_vec(&data) {
+            Ok(output) => Ok(PushResult::ok(data.len(), output)),
+            Err(err) => {
+                self.finished = true;
+                Ok(PushResult::error(format!("Snappy error: {}", err)))
+            }
+        }
+    }
+
+    /// Blocks are decoded as soon as they are pushed, so there is never anything pending and `data` is always
+    /// `null`.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        if self.disposed {
+            return Ok(FinishResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        self.finished = true;
+        Ok(FinishResult::ok(Vec::new()))
+    }
+
+    /// Makes the decompressor usable again after a stream has ended or a block failed to decode, keeping its
+    /// `maxBlockSize`.
+    ///
+    /// The raw snappy decoder keeps no state or buffers between blocks, since each block's output is allocated for it
+    /// and handed out by `push`. There is no allocation to keep or free, so resetting only clears the finished state.
+    #[napi]
+    pub fn reset(&mut self) -> Result<()> {
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, DECOMPRESSOR_DISPOSED));
+        }
+
+        self.finished = false;
+        Ok(())
+    }
+
+    /// Marks the decompressor as unusable. The decoder holds no resources of its own, so this only exists for parity
+    /// with the other decompressors.
+    ///
+    /// From then on `push` and `finish` fail with `Decompressor has been disposed`. Disposing more than once does
+    /// nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.finished = true;
+        self.disposed = true;
+    }
+}
+
+#[napi]
+pub struct SnappyCompressor {
+    encoder: Encoder,
+    // Most input a single block may hold, if limited
+    chunk_size: Option<u32>,
+    // Most output all pushes together may produce, if limited
+    max_output_bytes: Option<u32>,
+    // Running total of the output handed out by push
+    total_output: u64,
+    // Set once dispose was called
+    disposed: bool,
+}
+
+impl Default for SnappyCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[napi]
+impl SnappyCompressor {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self {
+            encoder: Encoder::new(),
+            chunk_size: None,
+            max_output_bytes: None,
+            total_output: 0,
+            disposed: false,
+        }
+    }
+
+    /// Creates a snappy compressor whose pushes may produce at most `maxOutputBytes` bytes between them, for
+    /// protocols with a hard limit on the size of a payload.
+    ///
+    /// A `push` that could take the total past the budget fails with `budget exceeded` before anything is compressed.
+    /// Since that is decided up front, it goes by the largest size the block could compress to, so a push may be
+    /// refused even though its actual output would have fit. Refused pushes leave the compressor as it was, so
+    /// smaller ones can still go through.
+    #[napi(factory)]
+    pub fn new_with_budget(max_output_bytes: u32) -> Result<Self> {
+        if max_output_bytes == 0 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                "Output budget must be greater than 0",
+            ));
+        }
+
+        Ok(Self {
+            max_output_bytes: Some(max_output_bytes),
+            ..Self::new()
+        })
+    }
+
+    /// Limits how many bytes of input a single block may hold, such as to keep blocks within the `maxBlockSize` of the
+    /// `SnappyDecompressor` on the other end, or to keep them small for latency.
+    ///
+    /// Every `push` compresses a whole block at once, so there is never a block in progress and the new size applies
+    /// from the next `push` on.
+    #[napi]
+    pub fn set_chunk_size(&mut self, chunk_size: u32) -> Result<()> {
+        validate_chunk_size(chunk_size)?;
+
+        if self.disposed {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        self.chunk_size = Some(chunk_size);
+        Ok(())
+    }
+
+    /// Compresses `data` into one raw snappy block.
+    ///
+    /// Snappy is block-oriented, so every `push` produces a complete block that can be decompressed on its own, with
+    /// no state carried over between calls and nothing to flush at the end. `bytesConsumed` is always the length of
+    /// `data`. Once `setChunkSize` was called, `data` larger than the chunk size fails instead, as does `data` that
+    /// could go over the budget of a compressor created by `newWithBudget`.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        if self.disposed {
+            return Ok(PushResult::error(COMPRESSOR_DISPOSED));
+        }
+
+        if let Some(chunk_size) = self.chunk_size {
+            if data.len() > chunk_size as usize {
+                return Ok(PushResult::error(format!(
+                    "Block of {} bytes is larger than the chunk size of {}",
+                    data.len(),
+                    chunk_size
+                )));
+            }
+        }
+
+        if let Some(max_output_bytes) = self.max_output_bytes {
+            if self.total_output + max_compress_len(data.len()) as u64 > u64::from(max_output_bytes)
+            {
+                return Ok(PushResult::error("budget exceeded"));
+            }
+        }
+
+        match self.encoder.compress_vec(&data) {
+            Ok(output) => {
+                self.total_output += output.len() as u64;
+                Ok(PushResult::ok(data.len(), output))
+            }
+            Err(err) => Ok(PushResult::error(format!("Snappy error: {}", err))),
+        }
+    }
+
+    /// Marks the compressor as unusable. The encoder holds no resources of its own, so this only exists for parity
+    /// with the other compressors.
+    ///
+    /// From then on `push` fails with `Compressor has been disposed`. Disposing more than once does nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.disposed = true;
+    }
+}
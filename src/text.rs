@@ -0,0 +1,84 @@
+use crate::options::DecompressorOptions;
+use crate::zlib::ZlibDecompressor;
+use napi::bindgen_prelude::{Either, Result, Uint8Array};
+
+/// Result of pushing data into a `TextDecompressor`.
+///
+/// `text` is only there once a message was inflated, and `finished` tells whether the underlying stream has ended or
+/// hit a terminal error. Failed results carry `error`.
+#[napi(object, object_from_js = false)]
+pub struct TextPushResult {
+    pub ok: bool,
+    pub text: Option<String>,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+#[napi]
+pub struct TextDecompressor {
+    inner: ZlibDecompressor,
+}
+
+#[napi]
+impl TextDecompressor {
+    /// Creates a zlib decompressor that hands out each inflated message as a UTF-8 string, taking the same arguments
+    /// as `ZlibDecompressor`.
+    #[napi(constructor)]
+    pub fn new(
+        options: Option<Either<u32, DecompressorOptions>>,
+        window_bits: Option<i32>,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: ZlibDecompressor::new(options, window_bits)?,
+        })
+    }
+
+    /// Like `ZlibDecompressor.push`, but decodes the inflated message as UTF-8 instead of returning it as a `Buffer`.
+    ///
+    /// A message that isn't valid UTF-8 fails with `Invalid UTF-8` and is dropped, while the stream itself carries on
+    /// with the next one.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<TextPushResult> {
+        let result = self.inner.push(data)?;
+        let finished = self.inner.is_finished();
+
+        if !result.ok {
+            return Ok(TextPushResult {
+                ok: false,
+                text: None,
+                finished,
+                error: result.error,
+            });
+        }
+
+        let Either::A(buffer) = result.data else {
+            return Ok(TextPushResult {
+                ok: true,
+                text: None,
+                finished,
+                error: None,
+            });
+        };
+
+        Ok(match std::str::from_utf8(&buffer) {
+            Ok(text) => TextPushResult {
+                ok: true,
+                text: Some(text.to_owned()),
+                finished,
+                error: None,
+            },
+            Err(err) => TextPushResult {
+                ok: false,
+                text: None,
+                finished,
+                error: Some(format!("Invalid UTF-8: {}", err)),
+            },
+        })
+    }
+
+    /// Tears down the underlying `ZlibDecompressor`, see `ZlibDecompressor.dispose`.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.inner.dispose();
+    }
+}
@@ -0,0 +1,277 @@
+//! The `ZlibDecompressor` surface for WebAssembly, exposed through wasm-bindgen instead of Node-API.
+//!
+//! Both this and the Node-API class wrap the same [`Inflater`], so both builds inflate the exact same way. Input is
+//! taken as a plain byte slice (a `Uint8Array` on the JavaScript side) and output is handed back as a `Uint8Array`
+//! instead of a `Buffer`, which only exists in Node.js. `pushShared` and `lastPushDurationNs` are Node.js only.
+
+use crate::inflater::{Inflater, InflaterError, InflaterOptions, PushOutcome};
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(err: InflaterError) -> JsError {
+    JsError::new(&err.message)
+}
+
+/// The outcome of a `push`, shaped like the Node-API `PushResult`.
+#[wasm_bindgen(js_name = PushResult, getter_with_clone)]
+pub struct WasmPushResult {
+    pub ok: bool,
+    #[wasm_bindgen(js_name = bytesConsumed)]
+    pub bytes_consumed: Option<u32>,
+    pub data: Option<Uint8Array>,
+    pub error: Option<String>,
+}
+
+impl From<PushOutcome> for WasmPushResult {
+    fn from(outcome: PushOutcome) -> Self {
+        match outcome {
+            PushOutcome::Ok {
+                bytes_consumed,
+                output,
+            } => Self {
+                ok: true,
+                bytes_consumed: Some(bytes_consumed as u32),
+                data: (!output.is_empty()).then(|| Uint8Array::from(output.as_slice())),
+                error: None,
+            },
+            PushOutcome::Failed(message) => Self {
+                ok: false,
+                bytes_consumed: None,
+                data: None,
+                error: Some(message),
+            },
+        }
+    }
+}
+
+/// The outcome of an `inflateSync`, shaped like the Node-API `SyncResult`.
+#[wasm_bindgen(js_name = SyncResult)]
+pub struct WasmSyncResult {
+    /// Whether a flush point was found
+    pub ok: bool,
+    /// How many bytes of corrupted input were skipped to get there
+    #[wasm_bindgen(js_name = bytesSkipped)]
+    pub bytes_skipped: u32,
+}
+
+/// Snapshot of the stream's counters, shaped like the Node-API `StreamStats`.
+#[wasm_bindgen(js_name = StreamStats)]
+pub struct WasmStreamStats {
+    #[wasm_bindgen(js_name = totalIn)]
+    pub total_in: f64,
+    #[wasm_bindgen(js_name = totalOut)]
+    pub total_out: f64,
+    pub adler: u32,
+    /// `totalOut / totalIn`, or 0 if nothing has been read yet
+    pub ratio: f64,
+}
+
+#[wasm_bindgen(js_name = ZlibDecompressor)]
+pub struct WasmZlibDecompressor {
+    inner: Inflater,
+}
+
+#[wasm_bindgen(js_class = ZlibDecompressor)]
+impl WasmZlibDecompressor {
+    /// Creates a zlib decompressor from an optional chunk size, window bits, preset dictionary and output limit.
+    /// Without arguments, a 16 KiB chunk size and 15 window bits are used.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        chunk_size: Option<u32>,
+        window_bits: Option<i32>,
+        dictionary: Option<Vec<u8>>,
+        max_output_size: Option<u32>,
+    ) -> Result<WasmZlibDecompressor, JsError> {
+        let inner = Inflater::with_options(InflaterOptions {
+            chunk_size,
+            window_bits,
+            dictionary,
+            max_output_size,
+        })
+        .map_err(to_js_error)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Creates a zlib decompressor whose LZ77 window holds at least `nBytes` bytes of history, see the Node-API
+    /// `ZlibDecompressor.withLz77HistorySize`.
+    #[wasm_bindgen(js_name = withLz77HistorySize)]
+    pub fn with_lz77_history_size(
+        chunk_size: u32,
+        n_bytes: u32,
+    ) -> Result<WasmZlibDecompressor, JsError> {
+        let inner = Inflater::with_lz77_history_size(chunk_size, n_bytes).map_err(to_js_error)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Creates a zlib decompressor that detects whether the stream is zlib or gzip-wrapped by itself (47 window bits).
+    #[wasm_bindgen(js_name = autoDetect)]
+    pub fn auto_detect(chunk_size: Option<u32>) -> Result<WasmZlibDecompressor, JsError> {
+        let inner = Inflater::auto_detect(chunk_size).map_err(to_js_error)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Switches the stream over to `bits` window bits, resetting it and discarding any buffered input and history.
+    #[wasm_bindgen(js_name = setWindowBits)]
+    pub fn set_window_bits(&mut self, bits: i32) -> Result<(), JsError> {
+        self.inner.set_window_bits(bits).map_err(to_js_error)
+    }
+
+    /// Sets how many bytes a single call may produce. Takes effect from the next call on.
+    #[wasm_bindgen(js_name = setOutputLimit)]
+    pub fn set_output_limit(&mut self, bytes: u32) {
+        self.inner.set_output_limit(Some(bytes));
+    }
+
+    /// Removes the output limit, if there was one.
+    #[wasm_bindgen(js_name = clearOutputLimit)]
+    pub fn clear_output_limit(&mut self) {
+        self.inner.set_output_limit(None);
+    }
+
+    /// Buffers `data` until a full sync-flushed message is available, then inflates it.
+    ///
+    /// `bytesConsumed` is how many buffered bytes inflate consumed during this call. It is 0 while a message is still
+    /// being buffered.
+    pub fn push(&mut self, data: &[u8]) -> Result<WasmPushResult, JsError> {
+        self.inner
+            .push(data)
+            .map(WasmPushResult::from)
+            .map_err(to_js_error)
+    }
+
+    /// Inflates the next complete sync-flushed message still sitting in the internal buffer, or returns `undefined`
+    /// if more data is needed. Inflate failures throw and finish the decompressor.
+    #[wasm_bindgen(js_name = decompressFrame)]
+    pub fn decompress_frame(&mut self) -> Result<Option<Uint8Array>, JsError> {
+        self.inner
+            .decompress_frame()
+            .map(|message| message.map(|output| Uint8Array::from(output.as_slice())))
+            .map_err(to_js_error)
+    }
+
+    /// Like `push`, but gives up on the stream once its running expansion ratio (`totalOut / totalIn`) goes above
+    /// `maxRatio`, failing with `ratio limit exceeded`.
+    #[wasm_bindgen(js_name = safeDecompress)]
+    pub fn safe_decompress(
+        &mut self,
+        data: &[u8],
+        max_ratio: f64,
+    ) -> Result<WasmPushResult, JsError> {
+        self.inner
+            .safe_decompress(data, max_ratio)
+            .map(WasmPushResult::from)
+            .map_err(to_js_error)
+    }
+
+    /// How many sync-flushed messages `push`, `safeDecompress` and `decompressFrame` have inflated successfully.
+    #[wasm_bindgen(getter, js_name = framesProcessed)]
+    pub fn frames_processed(&self) -> f64 {
+        self.inner.frames_processed() as f64
+    }
+
+    /// Inflates a message that arrived as an array of `Uint8Array` fragments in one go, after anything already
+    /// buffered by `push`. No sync-flush suffix is required and failures are thrown.
+    #[wasm_bindgen(js_name = inflateCoalesce)]
+    pub fn inflate_coalesce(&mut self, data: Array) -> Result<Uint8Array, JsError> {
+        let fragments: Vec<Vec<u8>> = data
+            .iter()
+            .map(|fragment| Uint8Array::new(&fragment).to_vec())
+            .collect();
+        let fragments: Vec<&[u8]> = fragments.iter().map(Vec::as_slice).collect();
+
+        self.inner
+            .inflate_coalesce(&fragments)
+            .map(|output| Uint8Array::from(output.as_slice()))
+            .map_err(to_js_error)
+    }
+
+    /// How many bytes are buffered while waiting for a sync-flush suffix.
+    #[wasm_bindgen(getter, js_name = inputBufferLen)]
+    pub fn input_buffer_len(&self) -> u32 {
+        self.inner.input_buffer_len()
+    }
+
+    /// Discards the input buffered while waiting for a sync-flush suffix, leaving the stream itself untouched.
+    #[wasm_bindgen(js_name = clearBuffer)]
+    pub fn clear_buffer(&mut self) {
+        self.inner.clear_buffer();
+    }
+
+    /// Where the sync-flush suffix started in the last complete message inflated, or `undefined` if there hasn't been
+    /// one yet.
+    #[wasm_bindgen(getter, js_name = suffixPosition)]
+    pub fn suffix_position(&self) -> Option<u32> {
+        self.inner.suffix_position()
+    }
+
+    /// How much input the last inflate call left unconsumed (the stream's `avail_in`).
+    #[wasm_bindgen(getter, js_name = inputRemaining)]
+    pub fn input_remaining(&self) -> u32 {
+        self.inner.input_remaining()
+    }
+
+    /// The message zlib reported for the last inflate error, if any.
+    #[wasm_bindgen(getter, js_name = lastErrorMessage)]
+    pub fn last_error_message(&self) -> Option<String> {
+        self.inner.last_error_message().map(str::to_string)
+    }
+
+    /// Checks whether pushing `data` would succeed, without modifying this decompressor.
+    #[wasm_bindgen(js_name = testPush)]
+    pub fn test_push(&mut self, data: &[u8]) -> Result<bool, JsError> {
+        self.inner.test_push(data).map_err(to_js_error)
+    }
+
+    /// The decompressor's current state as a single-line JSON object, for logging.
+    #[wasm_bindgen(js_name = metricsJson)]
+    pub fn metrics_json(&self) -> String {
+        self.inner.metrics_json()
+    }
+
+    /// Whether `other` has the same window bits and chunk size, and neither of them has finished or hit an error.
+    #[wasm_bindgen(js_name = compatibleWith)]
+    pub fn compatible_with(&self, other: &WasmZlibDecompressor) -> bool {
+        self.inner.compatible_with(&other.inner)
+    }
+
+    /// A human-readable dump of the decompressor's internal state, for crash reports.
+    #[wasm_bindgen(js_name = createDebugDump)]
+    pub fn create_debug_dump(&self) -> String {
+        self.inner.create_debug_dump()
+    }
+
+    /// Skips ahead to the next flush point in corrupted input, so decompression can pick up again from there
+    /// (`inflateSync`).
+    #[wasm_bindgen(js_name = inflateSync)]
+    pub fn inflate_sync(&mut self, data: &[u8]) -> Result<WasmSyncResult, JsError> {
+        let outcome = self.inner.inflate_sync(data).map_err(to_js_error)?;
+
+        Ok(WasmSyncResult {
+            ok: outcome.found,
+            bytes_skipped: outcome.bytes_skipped,
+        })
+    }
+
+    /// Snapshot of the stream's counters, read in a single call.
+    pub fn stats(&self) -> WasmStreamStats {
+        let stats = self.inner.stats();
+
+        WasmStreamStats {
+            total_in: stats.total_in as f64,
+            total_out: stats.total_out as f64,
+            adler: stats.adler,
+            ratio: stats.ratio,
+        }
+    }
+
+    /// Tears the inflate stream down right away, without freeing the object itself.
+    ///
+    /// From then on `push` and `safeDecompress` fail with `Decompressor has been disposed`, and the methods that
+    /// throw on failure throw it instead. Disposing more than once does nothing.
+    pub fn dispose(&mut self) {
+        self.inner.dispose();
+    }
+}
@@ -1,191 +1,295 @@
-use napi::bindgen_prelude::{Buffer, Env, Result, Status};
-use napi::Error;
-use std::ptr::NonNull;
-use zlib_rs::{
-    c_api::z_stream,
-    inflate::{self, InflateConfig, InflateStream},
-    InflateFlush, ReturnCode,
-};
+use crate::inflater::{Inflater, InflaterOptions};
+use crate::options::DecompressorOptions;
+use crate::result::PushResult;
+use napi::bindgen_prelude::{Buffer, Either, FromNapiValue, Result, Uint8Array};
+use napi::{Env, JsFunction, JsObject, NapiRaw};
 
-const Z_SYNC_FLUSH_SUFFIX: &[u8] = &[0, 0, 255, 255];
+#[napi(object, object_from_js = false)]
+pub struct StreamStats {
+    pub total_in: f64,
+    pub total_out: f64,
+    pub adler: u32,
+    /// `totalOut / totalIn`, or 0 if nothing has been read yet
+    pub ratio: f64,
+}
+
+#[napi(object, object_from_js = false)]
+pub struct SyncResult {
+    /// Whether a flush point was found
+    pub ok: bool,
+    /// How many bytes of corrupted input were skipped to get there
+    pub bytes_skipped: u32,
+}
 
+// The Node-API face of Inflater, which does the actual inflating
 #[napi]
-struct ZlibDecompressor {
-    chunk_size: u32,
-    // Pointer to the heap-allocated z_stream
-    stream_ptr: NonNull<z_stream>,
-    // Buffer for incoming data until Z_SYNC_FLUSH
-    internal_buffer: Vec<u8>,
-    // Track finished state separately (for terminal errors or unexpected StreamEnd)
-    finished: bool,
+pub struct ZlibDecompressor {
+    inner: Inflater,
 }
 
-impl Drop for ZlibDecompressor {
-    fn drop(&mut self) {
-        // SAFETY: NonNull guarantees that the stream_ptr is valid. Additionally, since this is the Drop trait,
-        // we should have no problems with double-frees or dangling pointers.
-        unsafe {
-            let _ = Box::from_raw(self.stream_ptr.as_ptr());
-        }
+impl ZlibDecompressor {
+    /// Creates a zlib decompressor from an options object. This is what the constructor ends up calling.
+    pub fn with_options(options: DecompressorOptions) -> Result<Self> {
+        let inner = Inflater::with_options(InflaterOptions {
+            chunk_size: options.chunk_size,
+            window_bits: options.window_bits,
+            dictionary: options.dictionary.map(|dictionary| dictionary.to_vec()),
+            max_output_size: options.max_output_size,
+        })?;
+
+        Ok(Self { inner })
+    }
+
+    /// Whether the stream ended or hit a terminal error, after which `push` ignores any further input.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.inner.is_finished()
     }
 }
 
 #[napi]
 impl ZlibDecompressor {
+    /// Creates a zlib decompressor from a chunk size and optional window bits, or from a `DecompressorOptions`
+    /// object. Without arguments, a 16 KiB chunk size and 15 window bits are used.
     #[napi(constructor)]
-    pub fn new(chunk_size: u32) -> Result<Self> {
-        let mut stream = Box::new(z_stream::default());
-
-        // Initialize the stream for inflation
-        let config = InflateConfig::default(); // Use default window bits
-        let ret_code = inflate::init(&mut *stream, config);
-        if ret_code != ReturnCode::Ok {
-            return Err(Error::new(
-                Status::GenericFailure,
-                format!("Failed to initialize inflate stream: {:?}", ret_code),
-            ));
-        }
+    pub fn new(
+        options: Option<Either<u32, DecompressorOptions>>,
+        window_bits: Option<i32>,
+    ) -> Result<Self> {
+        Self::with_options(DecompressorOptions::from_args(options, window_bits))
+    }
 
-        let stream_ptr = NonNull::new(Box::into_raw(stream)).ok_or_else(|| {
-            // If this fails, something is very wrong (Box::into_raw returning null?)
-            // We might need some manual deallocation logic here, but it's very complex so let's just pray for the best.
-            Error::new(
-                Status::GenericFailure,
-                "Failed to get stream pointer after init",
-            )
-        })?;
+    /// Creates a zlib decompressor whose LZ77 window holds at least `n_bytes` bytes of history.
+    ///
+    /// The size is rounded up to the next valid window size (a power of two between 256 bytes and 32 KiB), so
+    /// `n_bytes` must be within 1-32768.
+    #[napi(factory)]
+    pub fn with_lz77_history_size(chunk_size: u32, n_bytes: u32) -> Result<Self> {
+        Ok(Self {
+            inner: Inflater::with_lz77_history_size(chunk_size, n_bytes)?,
+        })
+    }
 
+    /// Creates a zlib decompressor that detects whether the stream is zlib or gzip-wrapped by itself (47 window bits).
+    ///
+    /// The wrapper is told apart by the first two bytes of the stream, which inflate waits for if they are split
+    /// across calls. Raw deflate streams can't be detected and fail as invalid.
+    #[napi(factory)]
+    pub fn auto_detect(chunk_size: Option<u32>) -> Result<Self> {
         Ok(Self {
-            stream_ptr,
-            chunk_size,
-            internal_buffer: Vec::new(),
-            finished: false,
+            inner: Inflater::auto_detect(chunk_size)?,
         })
     }
 
-    #[napi(ts_return_type = "{ ok: true; data?: Buffer; } | { ok: false; error: string }")]
-    pub fn push(&mut self, env: Env, data: Buffer) -> Result<napi::JsObject> {
-        if self.finished {
-            // Already finished (due to error or StreamEnd), return early
-            let mut result_obj = env.create_object()?;
-            result_obj.set_named_property("ok", env.get_boolean(true)?)?;
-            return Ok(result_obj);
-        }
+    /// Switches the stream over to `bits` window bits, for protocols that negotiate them after the decompressor was
+    /// created.
+    ///
+    /// This resets the stream (reusing its allocations), so any buffered input and history is discarded and a
+    /// finished decompressor becomes usable again.
+    #[napi]
+    pub fn set_window_bits(&mut self, bits: i32) -> Result<()> {
+        Ok(self.inner.set_window_bits(bits)?)
+    }
 
-        // Append new data to the internal buffer
-        self.internal_buffer.extend_from_slice(&data);
+    /// Sets how many bytes a single call may produce, like the `maxOutputSize` option. Takes effect from the next
+    /// call on.
+    #[napi]
+    pub fn set_output_limit(&mut self, bytes: u32) {
+        self.inner.set_output_limit(Some(bytes));
+    }
 
-        // Check if the buffer ends with the Z_SYNC_FLUSH suffix
-        if !self.internal_buffer.ends_with(Z_SYNC_FLUSH_SUFFIX) {
-            let mut result_obj = env.create_object()?;
-            result_obj.set_named_property("ok", env.get_boolean(true)?)?;
-            return Ok(result_obj);
-        }
+    /// Removes the output limit, if there was one.
+    #[napi]
+    pub fn clear_output_limit(&mut self) {
+        self.inner.set_output_limit(None);
+    }
 
-        // Flush suffix; take the buffer content for decompression
-        let decompress = std::mem::take(&mut self.internal_buffer);
-
-        // SAFETY: stream_ptr is valid and there is no way for there to be simultaneous writes to it.
-        let stream = unsafe { self.stream_ptr.as_mut() };
-
-        let mut input_chunk: &[u8] = &decompress;
-        let mut output_buffer = Vec::new();
-        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
-        // Track if StreamEnd is hit unexpectedly
-        let mut current_run_finished = false;
-
-        while !input_chunk.is_empty() {
-            stream.next_in = input_chunk.as_ptr() as *mut u8;
-            stream.avail_in = input_chunk
-                .len()
-                .try_into()
-                .map_err(|_| Error::new(Status::GenericFailure, "Input chunk too large"))?;
-
-            loop {
-                stream.next_out = temp_out_buf.as_mut_ptr();
-                stream.avail_out = temp_out_buf.len().try_into().map_err(|_| {
-                    Error::new(Status::GenericFailure, "Output chunk size too large")
-                })?;
-
-                let total_out_before_inflate = stream.total_out;
-                let avail_in_before_inflate = stream.avail_in;
-
-                // SAFETY: Our pointers are all valid
-                let result_code = match unsafe { InflateStream::from_stream_mut(stream) } {
-                    Some(inflate_stream_ref) => unsafe {
-                        inflate::inflate(inflate_stream_ref, InflateFlush::NoFlush)
-                    },
-                    None => {
-                        self.finished = true;
-                        let mut error_obj = env.create_object()?;
-                        error_obj.set_named_property("ok", env.get_boolean(false)?)?;
-                        error_obj.set_named_property(
-                            "error",
-                            env.create_string("Failed to get inflate stream reference")?,
-                        )?;
-                        return Ok(error_obj);
-                    }
-                };
-
-                let written_in_call = (stream.total_out - total_out_before_inflate) as usize;
-                if written_in_call > 0 {
-                    let actual_written = std::cmp::min(written_in_call, temp_out_buf.len());
-                    output_buffer.extend_from_slice(&temp_out_buf[..actual_written]);
-                }
-
-                let consumed_in_call = (avail_in_before_inflate - stream.avail_in) as usize;
-                input_chunk = &input_chunk[consumed_in_call..];
-
-                match result_code {
-                    ReturnCode::Ok => {
-                        if stream.avail_out == 0 {
-                            continue;
-                        }
-
-                        break;
-                    }
-                    // Discord shouldn't do this, but we handle it regardless
-                    ReturnCode::StreamEnd => {
-                        self.finished = true;
-                        current_run_finished = true;
-                        break;
-                    }
-                    // Should not happen with NoFlush, treat as unexpected or break
-                    ReturnCode::BufError => {
-                        // Assume it means output buffer is full
-                        if stream.avail_out == 0 {
-                            continue;
-                        }
-                        break;
-                    }
-                    other_code => {
-                        self.finished = true;
-                        let mut error_obj = env.create_object()?;
-                        error_obj.set_named_property("ok", env.get_boolean(false)?)?;
-                        error_obj.set_named_property(
-                            "error",
-                            env.create_string(&format!("Inflate error: {:?}", other_code))?,
-                        )?;
-
-                        return Ok(error_obj);
-                    }
-                }
-            }
-
-            if current_run_finished {
-                break;
-            }
-        }
+    /// Buffers `data` until a full sync-flushed message is available, then inflates it.
+    ///
+    /// `bytesConsumed` is how many buffered bytes inflate consumed during this call. It is 0 while a message is still
+    /// being buffered. `data` is always copied, so the caller's buffer can be reused as soon as this returns.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        Ok(self.inner.push(&data)?.into())
+    }
+
+    /// Like `push`, but takes a whole `ArrayBuffer` or `SharedArrayBuffer` rather than a view into one.
+    ///
+    /// N-API can't read a `SharedArrayBuffer` directly, so it is wrapped in a `Uint8Array` first. Just like with
+    /// `push`, the data is copied before inflating, so inflate never reads from memory other threads may be writing to.
+    #[napi(ts_args_type = "data: ArrayBuffer | SharedArrayBuffer")]
+    pub fn push_shared(&mut self, env: Env, data: JsObject) -> Result<PushResult> {
+        let uint8_array_constructor: JsFunction =
+            env.get_global()?.get_named_property("Uint8Array")?;
+        let view = uint8_array_constructor.new_instance(&[data])?;
+
+        // SAFETY: view is a live Uint8Array, just created above
+        let view = unsafe { Uint8Array::from_napi_value(env.raw(), view.raw()) }?;
 
-        let mut result_obj = env.create_object()?;
-        result_obj.set_named_property("ok", env.get_boolean(true)?)?;
-        if !output_buffer.is_empty() {
-            result_obj.set_named_property(
-                "data",
-                env.create_buffer_with_data(output_buffer)?.into_raw(),
-            )?;
+        self.push(view)
+    }
+
+    /// Inflates the next complete sync-flushed message still sitting in the internal buffer, or returns `null` if
+    /// more data is needed.
+    ///
+    /// `push` only inflates once the buffer ends with the sync flush suffix, so a push carrying one message and the
+    /// start of the next leaves the complete one buffered. This pulls such messages out one at a time, returning an
+    /// empty buffer for messages that inflate to nothing. Inflate failures throw and finish the decompressor.
+    ///
+    /// The suffix bytes can also turn up inside compressed data, so a suffix in the middle of the buffer only ends a
+    /// message if inflating up to it leaves the stream between two blocks, which is checked on a copy of the stream.
+    #[napi]
+    pub fn decompress_frame(&mut self) -> Result<Option<Buffer>> {
+        Ok(self.inner.decompress_frame()?.map(Buffer::from))
+    }
+
+    /// Like `push`, but gives up on the stream once its running expansion ratio (`totalOut / totalIn`) goes above
+    /// `maxRatio`, as a defense against decompression bombs.
+    ///
+    /// The ratio is checked after every inflate call, so at most one `chunkSize` worth of output is produced past the
+    /// limit. Exceeding it fails with `ratio limit exceeded` and finishes the decompressor.
+    #[napi]
+    pub fn safe_decompress(&mut self, data: Uint8Array, max_ratio: f64) -> Result<PushResult> {
+        Ok(self.inner.safe_decompress(&data, max_ratio)?.into())
+    }
+
+    /// How long the last `push` or `decompressFrame` call spent inflating, in nanoseconds. Calls that only buffered
+    /// their input report 0.
+    #[napi(getter)]
+    pub fn last_push_duration_ns(&self) -> f64 {
+        self.inner.last_push_duration_ns() as f64
+    }
+
+    /// How many sync-flushed messages `push`, `safeDecompress` and `decompressFrame` have inflated successfully, for
+    /// tracking throughput.
+    #[napi(getter)]
+    pub fn frames_processed(&self) -> f64 {
+        self.inner.frames_processed() as f64
+    }
+
+    /// Inflates a message that arrived in several fragments (e.g. fragmented WebSocket frames) in one go.
+    ///
+    /// The fragments are fed to inflate one after another, after anything already buffered by `push`, without being
+    /// concatenated first. Unlike `push`, no sync-flush suffix is required and failures are thrown.
+    #[napi]
+    pub fn inflate_coalesce(&mut self, data: Vec<Buffer>) -> Result<Buffer> {
+        let fragments: Vec<&[u8]> = data.iter().map(|chunk| &chunk[..]).collect();
+
+        Ok(self.inner.inflate_coalesce(&fragments)?.into())
+    }
+
+    /// How many bytes are buffered while waiting for a sync-flush suffix.
+    #[napi(getter)]
+    pub fn input_buffer_len(&self) -> u32 {
+        self.inner.input_buffer_len()
+    }
+
+    /// Discards the input buffered while waiting for a sync-flush suffix, leaving the stream itself untouched.
+    ///
+    /// Meant for when the framing layer knows a message was cut short, such as by a dropped connection, but the
+    /// stream is still intact, so the partial message doesn't end up in front of the next one.
+    #[napi]
+    pub fn clear_buffer(&mut self) {
+        self.inner.clear_buffer();
+    }
+
+    /// Where the sync-flush suffix started in the last complete message `push`, `safeDecompress` or `decompressFrame`
+    /// inflated, or `null` if there hasn't been one yet.
+    ///
+    /// Messages always end with the suffix, so this is the message length minus 4.
+    #[napi(getter)]
+    pub fn suffix_position(&self) -> Option<u32> {
+        self.inner.suffix_position()
+    }
+
+    /// How much input the last inflate call left unconsumed (the stream's `avail_in`).
+    ///
+    /// This is usually 0. Anything else means inflate stopped early, typically because the stream ended with more
+    /// data behind it, such as a second concatenated stream, or because it hit an error.
+    #[napi(getter)]
+    pub fn input_remaining(&self) -> u32 {
+        self.inner.input_remaining()
+    }
+
+    /// The message zlib reported for the last inflate error, if any.
+    #[napi(getter)]
+    pub fn last_error_message(&self) -> Option<String> {
+        self.inner.last_error_message().map(str::to_string)
+    }
+
+    /// Checks whether pushing `data` would succeed, without modifying this decompressor.
+    ///
+    /// The pending buffered input plus `data` is inflated through a copy of the stream, regardless of whether it ends
+    /// with a sync-flush suffix yet. Returns `false` if the decompressor has already finished.
+    #[napi]
+    pub fn test_push(&mut self, data: Uint8Array) -> Result<bool> {
+        Ok(self.inner.test_push(&data)?)
+    }
+
+    /// The decompressor's current state as a single-line JSON object, for logging.
+    ///
+    /// Holds `totalIn`, `totalOut`, `ratio`, `finished`, `error` (the last inflate error message, or `null`),
+    /// `chunkSize` and `windowBits`.
+    #[napi]
+    pub fn metrics_json(&self) -> String {
+        self.inner.metrics_json()
+    }
+
+    /// Whether `other` is configured the same way as this decompressor, with the same window bits and chunk size, and
+    /// neither of them has finished or hit an error.
+    ///
+    /// Meant for pools checking that an idle decompressor fits a request before handing it out.
+    #[napi]
+    pub fn compatible_with(&self, other: &ZlibDecompressor) -> bool {
+        self.inner.compatible_with(&other.inner)
+    }
+
+    /// A human-readable dump of the decompressor's internal state, for crash reports.
+    ///
+    /// Lists the addresses of the stream and the buffers it last worked with next to its counters, as well as the
+    /// buffered input length, whether the decompressor finished and the last inflate error. Pointers are only printed,
+    /// never followed, so this is safe to call at any time, including after an error.
+    #[napi]
+    pub fn create_debug_dump(&self) -> String {
+        self.inner.create_debug_dump()
+    }
+
+    /// Skips ahead to the next flush point (the empty stored block written by a sync or full flush) in corrupted
+    /// input, so decompression can pick up again from there (`inflateSync`).
+    ///
+    /// The search covers any buffered input followed by `data`. Input after the flush point stays buffered for the
+    /// next `push`, and a decompressor that finished because of an error becomes usable again. If no flush point is
+    /// found, all of the input is skipped and the search carries on with the next call. History from before the
+    /// corruption is kept, so output from blocks referencing it can still be wrong.
+    #[napi]
+    pub fn inflate_sync(&mut self, data: Buffer) -> Result<SyncResult> {
+        let outcome = self.inner.inflate_sync(&data)?;
+
+        Ok(SyncResult {
+            ok: outcome.found,
+            bytes_skipped: outcome.bytes_skipped,
+        })
+    }
+
+    /// Snapshot of the stream's counters, read in a single call.
+    #[napi]
+    pub fn stats(&self) -> StreamStats {
+        let stats = self.inner.stats();
+
+        StreamStats {
+            total_in: stats.total_in as f64,
+            total_out: stats.total_out as f64,
+            adler: stats.adler,
+            ratio: stats.ratio,
         }
+    }
 
-        Ok(result_obj)
+    /// Tears the inflate stream down right away instead of waiting for the garbage collector, and drops any buffered
+    /// input.
+    ///
+    /// From then on `push` and `safeDecompress` fail with `Decompressor has been disposed`, and the methods that throw
+    /// on failure throw it instead. Disposing more than once does nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.inner.dispose();
     }
 }
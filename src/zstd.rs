@@ -0,0 +1,606 @@
+use crate::result::{FinishResult, PushResult, COMPRESSOR_DISPOSED, DECOMPRESSOR_DISPOSED};
+use ::zstd::stream::raw::{CParameter, DParameter, Encoder, InBuffer, Operation, OutBuffer};
+use ::zstd::zstd_safe::zstd_sys::{self, ZSTD_ErrorCode, ZSTD_strategy};
+use ::zstd::zstd_safe::{self, DCtx, FrameFormat};
+use napi::bindgen_prelude::{AsyncTask, Buffer, Env, Result, Status, Task, Uint8Array};
+use napi::Error;
+
+fn validate_chunk_size(chunk_size: u32) -> Result<()> {
+    if chunk_size == 0 {
+        return Err(Error::new(
+            Status::InvalidArg,
+            "Chunk size must be greater than 0",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_level(level: i32) -> Result<()> {
+    if !(-5..=22).contains(&level) {
+        return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid zstd level {}, expected -5-22", level),
+        ));
+    }
+
+    Ok(())
+}
+
+/// A zstd error, broken down for callers that want to handle some kinds of errors differently.
+#[napi(object, object_from_js = false)]
+#[derive(Clone)]
+pub struct ZstdError {
+    /// The `ZSTD_ErrorCode`, which is stable across zstd versions
+    pub code: u32,
+    /// zstd's description of the error
+    pub name: String,
+    /// Whether the input is corrupted, as opposed to being valid but unsupported or running out of resources
+    pub is_corruption: bool,
+    /// Whether zstd failed to allocate memory
+    pub is_oom: bool,
+}
+
+impl ZstdError {
+    /// Breaks down an error result returned by one of zstd's functions.
+    fn from_result(result: usize) -> Self {
+        // SAFETY: ZSTD_getErrorCode only looks at the value passed in
+        let code = unsafe { zstd_sys::ZSTD_getErrorCode(result) };
+
+        Self {
+            code: code as u32,
+            name: zstd_safe::get_error_name(result).to_owned(),
+            is_corruption: matches!(
+                code,
+                ZSTD_ErrorCode::ZSTD_error_corruption_detected
+                    | ZSTD_ErrorCode::ZSTD_error_checksum_wrong
+                    | ZSTD_ErrorCode::ZSTD_error_literals_headerWrong
+                    | ZSTD_ErrorCode::ZSTD_error_dictionary_corrupted
+            ),
+            is_oom: matches!(code, ZSTD_ErrorCode::ZSTD_error_memory_allocation),
+        }
+    }
+}
+
+#[napi]
+pub struct ZstdDecompressor {
+    chunk_size: u32,
+    // Used directly rather than through zstd's Decoder, which turns error codes into plain messages. Taken by dispose
+    decoder: Option<DCtx<'static>>,
+    // Set while a frame has been started but not completed yet
+    in_frame: bool,
+    // How many bytes the last `push` call produced
+    last_call_output_bytes: u64,
+    // Track finished state separately (for terminal errors or the end of the stream)
+    finished: bool,
+    // The error that finished the stream, if zstd reported one
+    last_error: Option<ZstdError>,
+}
+
+#[napi]
+impl ZstdDecompressor {
+    /// Creates a zstd decompressor, optionally loaded with the `dictionary` the frames were compressed with.
+    ///
+    /// The dictionary can be a trained zstd dictionary or raw content, and stays loaded for every frame that follows.
+    #[napi(constructor)]
+    pub fn new(chunk_size: u32, dictionary: Option<Buffer>) -> Result<Self> {
+        validate_chunk_size(chunk_size)?;
+
+        let mut decoder = DCtx::try_create().ok_or_else(|| {
+            Error::new(
+                Status::GenericFailure,
+                "Failed to initialize zstd decoder: could not allocate a decompression context",
+            )
+        })?;
+
+        if let Some(dictionary) = dictionary {
+            decoder.load_dictionary(&dictionary).map_err(|result| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!(
+                        "Failed to load zstd dictionary: {}",
+                        zstd_safe::get_error_name(result)
+                    ),
+                )
+            })?;
+        }
+
+        Ok(Self {
+            chunk_size,
+            decoder: Some(decoder),
+            in_frame: false,
+            last_call_output_bytes: 0,
+            finished: false,
+            last_error: None,
+        })
+    }
+
+    /// Decompresses as much of `data` as possible.
+    ///
+    /// Back-to-back frames are decompressed one after the other, so the stream only counts as complete once the last
+    /// frame pushed so far has ended.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        self.last_call_output_bytes = 0;
+
+        let Some(decoder) = self.decoder.as_mut() else {
+            return Ok(PushResult::error(DECOMPRESSOR_DISPOSED));
+        };
+
+        if self.finished {
+            // Already finished (due to error or end of stream), return early
+            return Ok(PushResult::ok(0, Vec::new()));
+        }
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let mut input = InBuffer::around(&data);
+
+        loop {
+            let mut output = OutBuffer::around(temp_out_buf.as_mut_slice());
+            let input_pos_before_run = input.pos();
+
+            let result = decoder.decompress_stream(&mut output, &mut input);
+
+            let written_in_call = output.pos();
+            output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+            self.last_call_output_bytes += written_in_call as u64;
+
+            match result {
+                // A hint of 0 means a frame was just completed
+                Ok(0) => self.in_frame = false,
+                // Calls that made no progress just hand out the hint for the next frame, which hasn't started yet
+                Ok(_) if input.pos() == input_pos_before_run && written_in_call == 0 => {}
+                Ok(_) => self.in_frame = true,
+                Err(result) => {
+                    let error = ZstdError::from_result(result);
+                    let message = format!("Zstd error: {}", error.name);
+
+                    self.finished = true;
+                    self.last_error = Some(error);
+                    return Ok(PushResult::error(message));
+                }
+            }
+
+            // The output chunk was filled, so the decoder may have more to give us
+            if written_in_call == temp_out_buf.len() {
+                continue;
+            }
+
+            if input.pos() == data.len() {
+                break;
+            }
+        }
+
+        Ok(PushResult::ok(input.pos(), output_buffer))
+    }
+
+    /// Switches between regular frames and frames with their 4 magic bytes stripped, as used by some custom framings.
+    ///
+    /// Magic-less frames can't be told apart from garbage, so only enable this when the framing is known for sure.
+    /// Must be called before the first `push`, or between frames.
+    #[napi]
+    pub fn allow_magic_less(&mut self, enable: bool) -> Result<()> {
+        let format = if enable {
+            FrameFormat::Magicless
+        } else {
+            FrameFormat::One
+        };
+
+        let decoder = self
+            .decoder
+            .as_mut()
+            .ok_or_else(|| Error::new(Status::GenericFailure, DECOMPRESSOR_DISPOSED))?;
+
+        decoder
+            .set_parameter(DParameter::Format(format))
+            .map(|_| ())
+            .map_err(|result| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!(
+                        "Failed to set zstd frame format: {}",
+                        zstd_safe::get_error_name(result)
+                    ),
+                )
+            })
+    }
+
+    /// The error that made `push` fail, broken down into its zstd error code and kind, or `null` if there was none.
+    #[napi(getter)]
+    pub fn structured_error(&self) -> Option<ZstdError> {
+        self.last_error.clone()
+    }
+
+    /// How many bytes the last `push` call produced, as opposed to the running total. Useful for spotting chunks that
+    /// decompress to suspiciously large outputs.
+    #[napi(getter)]
+    pub fn decompressed_bytes_this_call(&self) -> f64 {
+        self.last_call_output_bytes as f64
+    }
+
+    /// Checks that the stream did not end in the middle of a frame. All output is already handed out by `push`, so
+    /// `data` is always `null`.
+    ///
+    /// A stream that `push` already failed on fails again here with the same error.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        if self.decoder.is_none() {
+            return Ok(FinishResult::error(DECOMPRESSOR_DISPOSED));
+        }
+
+        if let Some(error) = &self.last_error {
+            return Ok(FinishResult::error(format!("Zstd error: {}", error.name)));
+        }
+
+        self.finished = true;
+
+        if self.in_frame {
+            return Ok(FinishResult::error("Unexpected end of zstd stream"));
+        }
+
+        Ok(FinishResult::ok(Vec::new()))
+    }
+
+    /// Frees the decompression context right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Decompressor has been disposed`, and `allowMagicLess` throws it.
+    /// Disposing more than once does nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.decoder = None;
+        self.finished = true;
+    }
+}
+
+#[napi]
+pub enum ZstdStrategy {
+    Fast = 1,
+    DFast = 2,
+    Greedy = 3,
+    Lazy = 4,
+    Lazy2 = 5,
+    BtLazy2 = 6,
+    BtOpt = 7,
+    BtUltra = 8,
+    BtUltra2 = 9,
+}
+
+impl From<ZSTD_strategy> for ZstdStrategy {
+    fn from(strategy: ZSTD_strategy) -> Self {
+        match strategy {
+            ZSTD_strategy::ZSTD_fast => ZstdStrategy::Fast,
+            ZSTD_strategy::ZSTD_dfast => ZstdStrategy::DFast,
+            ZSTD_strategy::ZSTD_greedy => ZstdStrategy::Greedy,
+            ZSTD_strategy::ZSTD_lazy => ZstdStrategy::Lazy,
+            ZSTD_strategy::ZSTD_lazy2 => ZstdStrategy::Lazy2,
+            ZSTD_strategy::ZSTD_btlazy2 => ZstdStrategy::BtLazy2,
+            ZSTD_strategy::ZSTD_btopt => ZstdStrategy::BtOpt,
+            ZSTD_strategy::ZSTD_btultra => ZstdStrategy::BtUltra,
+            ZSTD_strategy::ZSTD_btultra2 => ZstdStrategy::BtUltra2,
+        }
+    }
+}
+
+/// The parameters zstd derives from a compression level.
+#[napi(object, object_from_js = false)]
+pub struct ZstdCompressionParameters {
+    /// log2 of the largest match distance
+    pub window_log: u32,
+    /// log2 of the size of the match chain table
+    pub chain_log: u32,
+    /// log2 of the size of the hash table
+    pub hash_log: u32,
+    /// log2 of how many searches are made per position
+    pub search_log: u32,
+    /// Shortest match length searched for
+    pub min_match: u32,
+    /// Match length at which the optimal parser stops searching, or the acceleration factor for the fast strategy
+    pub target_length: u32,
+    pub strategy: ZstdStrategy,
+}
+
+pub struct ZstdCompressTask {
+    data: Vec<u8>,
+    level: i32,
+}
+
+impl Task for ZstdCompressTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        ::zstd::encode_all(self.data.as_slice(), self.level).map_err(|err| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to compress with zstd: {}", err),
+            )
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+#[napi]
+pub struct ZstdCompressor {
+    chunk_size: u32,
+    level: i32,
+    // Boxed so the compression context stays put on the heap, the same way the zlib streams do. Taken by dispose
+    encoder: Option<Box<Encoder<'static>>>,
+    // Set once the stream has been finished or hit a terminal error
+    finished: bool,
+}
+
+#[napi]
+impl ZstdCompressor {
+    /// Creates a zstd compressor. `level` must be within -5-22, where negative levels trade ratio for speed.
+    ///
+    /// With a `dictionary`, every frame is compressed against it, and the decompressor must be given the exact same
+    /// dictionary to read them back.
+    #[napi(constructor)]
+    pub fn new(chunk_size: u32, level: i32, dictionary: Option<Buffer>) -> Result<Self> {
+        validate_chunk_size(chunk_size)?;
+        validate_level(level)?;
+
+        let encoder = match dictionary {
+            Some(dictionary) => Encoder::with_dictionary(level, &dictionary),
+            None => Encoder::new(level),
+        }
+        .map_err(|err| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to initialize zstd encoder: {}", err),
+            )
+        })?;
+
+        Ok(Self {
+            chunk_size,
+            level,
+            encoder: Some(Box::new(encoder)),
+            finished: false,
+        })
+    }
+
+    /// Compresses `data` into a single complete zstd frame on a worker thread, so large inputs don't block the event
+    /// loop.
+    ///
+    /// `level` is validated right away like the constructor's, so an invalid level throws instead of rejecting.
+    /// `data` is copied before the task is queued.
+    #[napi]
+    pub fn compress_in_worker(data: Buffer, level: i32) -> Result<AsyncTask<ZstdCompressTask>> {
+        validate_level(level)?;
+
+        Ok(AsyncTask::new(ZstdCompressTask {
+            data: data.to_vec(),
+            level,
+        }))
+    }
+
+    /// The parameters zstd resolves this compressor's level to, assuming the input size is unknown.
+    #[napi]
+    pub fn get_compression_parameters(&self) -> ZstdCompressionParameters {
+        // SAFETY: ZSTD_getCParams only looks up the parameter tables, it doesn't touch any memory we own
+        let params = unsafe { zstd_sys::ZSTD_getCParams(self.level, 0, 0) };
+
+        ZstdCompressionParameters {
+            window_log: params.windowLog,
+            chain_log: params.chainLog,
+            hash_log: params.hashLog,
+            search_log: params.searchLog,
+            min_match: params.minMatch,
+            target_length: params.targetLength,
+            strategy: params.strategy.into(),
+        }
+    }
+
+    /// Declares the total size of the input up front, which lets zstd pick better parameters and record the size in
+    /// the frame header.
+    ///
+    /// Must be called before the first `push`. Finishing the stream fails if the pushed input doesn't add up to `size`.
+    #[napi]
+    pub fn set_pledged_src_size(&mut self, size: i64) -> Result<()> {
+        let size = u64::try_from(size).map_err(|_| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Invalid pledged source size {}", size),
+            )
+        })?;
+
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED))?;
+
+        encoder.set_pledged_src_size(Some(size)).map_err(|err| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to set pledged source size: {}", err),
+            )
+        })
+    }
+
+    /// Sets how much of the window each job of multi-threaded compression shares with the previous one
+    /// (`ZSTD_c_overlapLog`), within 0-9. 0 lets zstd pick based on the strategy, 1 means no overlap and 9 a full
+    /// window, with each step in between doubling it. A larger overlap improves the ratio at the cost of more memory.
+    ///
+    /// This only takes effect when zstd compresses with 2 or more workers (`nbWorkers >= 2`). This compressor doesn't
+    /// enable any, so on its own the setting has no effect on the output.
+    #[napi]
+    pub fn set_overlap_log(&mut self, log: u32) -> Result<()> {
+        if log > 9 {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("Invalid zstd overlap log {}, expected 0-9", log),
+            ));
+        }
+
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED))?;
+
+        encoder
+            .set_parameter(CParameter::OverlapSizeLog(log))
+            .map_err(|err| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Failed to set overlap log: {}", err),
+                )
+            })
+    }
+
+    /// Feeds `data` into the compressor. The encoder may hold on to input, so `data` may be `null` until `finish`.
+    #[napi]
+    pub fn push(&mut self, data: Uint8Array) -> Result<PushResult> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(PushResult::error(COMPRESSOR_DISPOSED));
+        };
+
+        if self.finished {
+            return Ok(PushResult::error("Compressor has already finished"));
+        }
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        let mut input = InBuffer::around(&data);
+
+        loop {
+            let mut output = OutBuffer::around(temp_out_buf.as_mut_slice());
+
+            let result = encoder.run(&mut input, &mut output);
+
+            let written_in_call = output.pos();
+            output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+
+            if let Err(err) = result {
+                self.finished = true;
+                return Ok(PushResult::error(format!("Zstd error: {}", err)));
+            }
+
+            // The output chunk was filled, so the encoder may have more to give us
+            if written_in_call == temp_out_buf.len() {
+                continue;
+            }
+
+            if input.pos() == data.len() {
+                break;
+            }
+        }
+
+        Ok(PushResult::ok(input.pos(), output_buffer))
+    }
+
+    /// Builds a complete zstd frame with no content, typically 9 bytes long, for protocols that send empty frames as
+    /// keep-alives or checkpoints.
+    ///
+    /// The frame is independent of this compressor's own stream, which is left untouched. A zstd stream is a sequence
+    /// of frames, so the empty frame can go before this compressor's output or after `finish`, but not in the middle
+    /// of a frame.
+    #[napi]
+    pub fn write_empty_block(&self) -> Result<Buffer> {
+        if self.encoder.is_none() {
+            return Err(Error::new(Status::GenericFailure, COMPRESSOR_DISPOSED));
+        }
+
+        ::zstd::bulk::compress(&[], self.level)
+            .map(Buffer::from)
+            .map_err(|err| {
+                Error::new(
+                    Status::GenericFailure,
+                    format!("Failed to write empty zstd frame: {}", err),
+                )
+            })
+    }
+
+    /// Completes the frame, returning everything the encoder was still holding on to.
+    #[napi]
+    pub fn finish(&mut self) -> Result<FinishResult> {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(FinishResult::error(COMPRESSOR_DISPOSED));
+        };
+
+        if self.finished {
+            return Ok(FinishResult::error("Compressor has already finished"));
+        }
+
+        self.finished = true;
+
+        let mut output_buffer = Vec::new();
+        let mut temp_out_buf = vec![0u8; self.chunk_size as usize];
+
+        loop {
+            let mut output = OutBuffer::around(temp_out_buf.as_mut_slice());
+
+            let result = encoder.finish(&mut output, true);
+
+            let written_in_call = output.pos();
+            output_buffer.extend_from_slice(&temp_out_buf[..written_in_call]);
+
+            match result {
+                // Nothing left to flush, the frame is complete
+                Ok(0) => return Ok(FinishResult::ok(output_buffer)),
+                Ok(_) => continue,
+                Err(err) => return Ok(FinishResult::error(format!("Zstd error: {}", err))),
+            }
+        }
+    }
+
+    /// Frees the compression context right away instead of waiting for the garbage collector.
+    ///
+    /// From then on `push` and `finish` fail with `Compressor has been disposed`, and `setPledgedSrcSize`,
+    /// `setOverlapLog` and `writeEmptyBlock` throw it. Disposing more than once does nothing.
+    #[napi]
+    pub fn dispose(&mut self) {
+        self.encoder = None;
+        self.finished = true;
+    }
+}
+
+/// Trains a zstd dictionary of up to `dict_size` bytes from `samples`, which should be representative of the data it
+/// will be used on (for example individual messages rather than whole streams).
+///
+/// The result can be passed to both `ZstdCompressor` and `ZstdDecompressor`. zstd needs a reasonable number of
+/// samples to work with, and fails if there are too few or they are too small.
+#[napi]
+pub fn train_zstd_dictionary(samples: Vec<Buffer>, dict_size: u32) -> Result<Buffer> {
+    ::zstd::dict::from_samples(&samples, dict_size as usize)
+        .map(Buffer::from)
+        .map_err(|err| {
+            Error::new(
+                Status::GenericFailure,
+                format!("Failed to train zstd dictionary: {}", err),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // AsyncTask doesn't hand its task back out, so the task is built like compressInWorker builds it and run with
+    // compute, which is what the thread pool calls
+
+    #[test]
+    fn compress_task_round_trips() {
+        let data = b"compress me on a worker thread, ".repeat(1000);
+
+        let mut task = ZstdCompressTask {
+            data: data.clone(),
+            level: 19,
+        };
+        let compressed = task.compute().unwrap();
+        assert!(compressed.len() < data.len());
+
+        assert_eq!(::zstd::decode_all(compressed.as_slice()).unwrap(), data);
+    }
+
+    #[test]
+    fn compress_in_worker_rejects_invalid_levels() {
+        assert!(ZstdCompressor::compress_in_worker(Vec::new().into(), 23).is_err());
+        assert!(ZstdCompressor::compress_in_worker(Vec::new().into(), -6).is_err());
+    }
+}
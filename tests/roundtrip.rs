@@ -0,0 +1,1061 @@
+//! Round-trips known inputs through the compressors and decompressors, calling the Rust side of the Node-API classes
+//! directly.
+
+use napi::bindgen_prelude::{Buffer, Either, Null, Uint8Array};
+use vladfrangu_dev_compression::deflate::{deflate_copy, DeflateCompressor, FlushMode, GzipHeader};
+use vladfrangu_dev_compression::format::{is_valid_zlib, CompressionFormat};
+use vladfrangu_dev_compression::http::HttpDecompressor;
+use vladfrangu_dev_compression::oneshot::{compress_deflate, decompress_all_frames};
+use vladfrangu_dev_compression::options::{CompressorOptions, DecompressorOptions};
+#[cfg(any(feature = "brotli", feature = "zstd", feature = "lz4"))]
+use vladfrangu_dev_compression::result::{FinishResult, PushResult};
+use vladfrangu_dev_compression::text::TextDecompressor;
+use vladfrangu_dev_compression::zlib::ZlibDecompressor;
+use zlib_rs::deflate::DeflateConfig;
+
+const Z_SYNC_FLUSH_SUFFIX: &[u8] = &[0, 0, 255, 255];
+
+fn sample() -> Vec<u8> {
+    b"The quick brown fox jumps over the lazy dog. "
+        .iter()
+        .cycle()
+        .take(64 * 1024)
+        .copied()
+        .collect()
+}
+
+fn into_bytes(data: Either<Buffer, Null>) -> Vec<u8> {
+    match data {
+        Either::A(buffer) => buffer.to_vec(),
+        Either::B(_) => Vec::new(),
+    }
+}
+
+/// Compresses `data` as the next message of the compressor's stream, ending it with a sync flush like
+/// `ZlibDecompressor` expects.
+fn flush_message(compressor: &mut DeflateCompressor, data: &[u8]) -> Vec<u8> {
+    let pushed = compressor.push(Uint8Array::new(data.to_vec())).unwrap();
+    assert!(pushed.ok);
+    let flushed = compressor.finish(FlushMode::Sync).unwrap();
+    assert!(flushed.ok);
+
+    let mut compressed = into_bytes(pushed.data);
+    compressed.extend(into_bytes(flushed.data));
+    assert!(compressed.ends_with(Z_SYNC_FLUSH_SUFFIX));
+
+    compressed
+}
+
+/// Compresses `data` into a new zlib stream holding a single sync-flushed message.
+fn sync_flushed(data: &[u8]) -> Vec<u8> {
+    flush_message(&mut DeflateCompressor::new(None, None).unwrap(), data)
+}
+
+/// Compresses `data` into a complete gzip member.
+fn gzipped(data: &[u8]) -> Vec<u8> {
+    let config = DeflateConfig {
+        window_bits: 31,
+        ..DeflateConfig::default()
+    };
+
+    let mut compressed = vec![0u8; zlib_rs::deflate::bound(None, data.len()) + 18];
+    let (written, ret_code) = zlib_rs::deflate::compress_slice(&mut compressed, data, config);
+    assert_eq!(ret_code, zlib_rs::ReturnCode::Ok);
+
+    written.to_vec()
+}
+
+#[test]
+fn compress_deflate_round_trips() {
+    let data = sample();
+
+    let compressed = compress_deflate(data.clone().into(), None).unwrap();
+    assert!(compressed.len() < data.len());
+
+    let frames = decompress_all_frames(compressed, CompressionFormat::Zlib).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].to_vec(), data);
+}
+
+#[test]
+fn is_valid_zlib_checks_the_adler32_trailer() {
+    let data = sample();
+    let compressed = compress_deflate(data.into(), None).unwrap().to_vec();
+    assert!(is_valid_zlib(compressed.clone().into()));
+
+    // Cut short, so there's no trailer to check yet
+    assert!(is_valid_zlib(
+        compressed[..compressed.len() / 2].to_vec().into()
+    ));
+    assert!(is_valid_zlib(
+        compressed[..compressed.len() - 2].to_vec().into()
+    ));
+    assert!(is_valid_zlib(sync_flushed(b"no trailer yet").into()));
+
+    let mut wrong_trailer = compressed.clone();
+    *wrong_trailer.last_mut().unwrap() ^= 0xff;
+    assert!(!is_valid_zlib(wrong_trailer.into()));
+
+    // A stored block whose length doesn't match its one's complement
+    assert!(!is_valid_zlib(
+        vec![0x78, 0x9c, 0x01, 0x05, 0x00, 0x00, 0x00].into()
+    ));
+}
+
+#[test]
+fn decompress_all_frames_splits_concatenated_streams() {
+    let first = compress_deflate(b"first".to_vec().into(), None).unwrap();
+    let second = compress_deflate(b"second".to_vec().into(), Some(9)).unwrap();
+
+    let mut data = first.to_vec();
+    data.extend_from_slice(&second);
+
+    let frames = decompress_all_frames(data.into(), CompressionFormat::Zlib).unwrap();
+    let frames: Vec<Vec<u8>> = frames.iter().map(|frame| frame.to_vec()).collect();
+    assert_eq!(frames, [b"first".to_vec(), b"second".to_vec()]);
+}
+
+#[test]
+fn http_decompressor_handles_one_byte_pushes() {
+    let data = sample();
+    let compressed = gzipped(&data);
+
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+    let mut output = Vec::new();
+
+    for byte in compressed {
+        let result = decompressor.push(Uint8Array::new(vec![byte])).unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        output.extend(into_bytes(result.data));
+    }
+
+    let result = decompressor.finish().unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    output.extend(into_bytes(result.data));
+
+    assert_eq!(output, data);
+}
+
+#[test]
+fn zlib_decompressor_handles_one_byte_pushes() {
+    let data = sample();
+    let compressed = sync_flushed(&data);
+
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+    let mut output = Vec::new();
+
+    let (last, rest) = compressed.split_last().unwrap();
+    for &byte in rest {
+        let result = decompressor.push(Uint8Array::new(vec![byte])).unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        assert_eq!(result.bytes_consumed, Some(0));
+    }
+
+    // Nothing is inflated until the suffix is complete
+    let result = decompressor.push(Uint8Array::new(vec![*last])).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    output.extend(into_bytes(result.data));
+
+    assert_eq!(output, data);
+    assert_eq!(decompressor.input_buffer_len(), 0);
+}
+
+#[test]
+fn zlib_decompressor_inflates_exact_sync_flushed_messages() {
+    let mut compressor = DeflateCompressor::new(None, None).unwrap();
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    for message in [&b"first message"[..], b"second message", b"third message"] {
+        let compressed = flush_message(&mut compressor, message);
+        let len = compressed.len();
+
+        let result = decompressor.push(Uint8Array::new(compressed)).unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        assert_eq!(result.bytes_consumed, Some(len as u32));
+        assert_eq!(into_bytes(result.data), message);
+        assert_eq!(decompressor.suffix_position(), Some(len as u32 - 4));
+    }
+}
+
+#[test]
+fn zlib_decompressor_decompress_frame_skips_suffixes_inside_messages() {
+    // Stored blocks keep the data as is, so the suffix shows up in the middle of the first message
+    let mut compressor = DeflateCompressor::new(None, Some(0)).unwrap();
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    let mut first_message = b"before ".to_vec();
+    first_message.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+    first_message.extend_from_slice(b" after");
+
+    let mut buffered = flush_message(&mut compressor, &first_message);
+    let second = flush_message(&mut compressor, b"second message");
+    buffered.extend_from_slice(&second[..second.len() / 2]);
+
+    let result = decompressor.push(Uint8Array::new(buffered)).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(result.bytes_consumed, Some(0));
+
+    let frame = decompressor.decompress_frame().unwrap().unwrap();
+    assert_eq!(frame.to_vec(), first_message);
+    assert!(decompressor.decompress_frame().unwrap().is_none());
+
+    let result = decompressor
+        .push(Uint8Array::new(second[second.len() / 2..].to_vec()))
+        .unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), b"second message");
+    assert_eq!(decompressor.frames_processed(), 2.0);
+}
+
+#[test]
+fn zlib_decompressor_test_push_leaves_the_stream_alone() {
+    let mut compressor = DeflateCompressor::new(None, None).unwrap();
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    let first = flush_message(&mut compressor, b"first message");
+    let result = decompressor.push(Uint8Array::new(first)).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+
+    let input_remaining = decompressor.input_remaining();
+    let debug_dump = decompressor.create_debug_dump();
+
+    let second = flush_message(&mut compressor, b"second message");
+    assert!(decompressor
+        .test_push(Uint8Array::new(second.clone()))
+        .unwrap());
+
+    assert_eq!(decompressor.input_remaining(), input_remaining);
+    assert_eq!(decompressor.create_debug_dump(), debug_dump);
+
+    let result = decompressor.push(Uint8Array::new(second)).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), b"second message");
+}
+
+#[test]
+fn zlib_decompressor_rejects_malformed_data() {
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    let mut garbage = b"definitely not zlib".to_vec();
+    garbage.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+
+    let result = decompressor.push(Uint8Array::new(garbage)).unwrap();
+    assert!(!result.ok);
+    assert!(result.error.is_some());
+
+    // The stream is finished after an error, so further input is ignored
+    let compressed = sync_flushed(b"too late");
+    let result = decompressor.push(Uint8Array::new(compressed)).unwrap();
+    assert!(result.ok);
+    assert_eq!(result.bytes_consumed, Some(0));
+}
+
+#[test]
+fn zlib_decompressor_rejects_invalid_options() {
+    for window_bits in [7, 17, 33, 48, -7, -16] {
+        let options = DecompressorOptions {
+            window_bits: Some(window_bits),
+            ..DecompressorOptions::default()
+        };
+
+        assert!(
+            ZlibDecompressor::with_options(options).is_err(),
+            "window bits {} were accepted",
+            window_bits
+        );
+    }
+}
+
+#[test]
+fn zlib_decompressor_accepts_window_bits_read_from_the_header() {
+    let data = sample();
+
+    // 16 is gzip and 32 is zlib/gzip auto-detection, both taking the window size from the stream itself
+    for (window_bits, compressed) in [
+        (16, gzipped(&data)),
+        (32, gzipped(&data)),
+        (32, sync_flushed(&data)),
+    ] {
+        let options = DecompressorOptions {
+            window_bits: Some(window_bits),
+            ..DecompressorOptions::default()
+        };
+        let mut decompressor = ZlibDecompressor::with_options(options).unwrap();
+
+        // Whole streams don't end with a sync flush, so they go through inflateCoalesce instead of push
+        let output = decompressor.inflate_coalesce(vec![Buffer::from(compressed)]);
+        assert_eq!(
+            output.map(|buffer| buffer.to_vec()).ok(),
+            Some(data.clone())
+        );
+    }
+}
+
+#[test]
+fn http_decompressor_rejects_truncated_streams() {
+    let compressed = gzipped(&sample());
+
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+
+    let result = decompressor
+        .push(Uint8Array::new(compressed[..compressed.len() / 2].to_vec()))
+        .unwrap();
+    assert!(result.ok, "{:?}", result.error);
+
+    let result = decompressor.finish().unwrap();
+    assert!(!result.ok);
+    assert_eq!(
+        result.error.as_deref(),
+        Some("Unexpected end of gzip stream")
+    );
+}
+
+#[test]
+fn http_decompressor_writes_the_whole_body_to_a_file() {
+    let data = sample();
+    let compressed = gzipped(&data);
+    let path = std::env::temp_dir().join(format!("http-decompressor-{}.txt", std::process::id()));
+
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+
+    let (head, rest) = compressed.split_at(100);
+    let result = decompressor.push(Uint8Array::new(head.to_vec())).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    let mut written = into_bytes(result.data);
+
+    decompressor
+        .write_to_file(
+            path.to_string_lossy().into_owned(),
+            Some(Uint8Array::new(rest.to_vec())),
+        )
+        .unwrap();
+
+    written.extend(std::fs::read(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn http_decompressor_does_not_finish_a_broken_body() {
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+
+    let result = decompressor
+        .push(Uint8Array::new(b"not gzip at all".to_vec()))
+        .unwrap();
+    assert!(!result.ok);
+
+    let result = decompressor.finish().unwrap();
+    assert!(!result.ok);
+
+    let path = std::env::temp_dir().join(format!(
+        "http-decompressor-broken-{}.txt",
+        std::process::id()
+    ));
+    let written = decompressor.write_to_file(path.to_string_lossy().into_owned(), None);
+    assert!(written.is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn http_decompressor_leaves_the_file_alone_without_output() {
+    let path = std::env::temp_dir().join(format!(
+        "http-decompressor-untouched-{}.txt",
+        std::process::id()
+    ));
+    std::fs::write(&path, b"keep me").unwrap();
+    let write_to_file = |decompressor: &mut HttpDecompressor, data: &[u8]| {
+        decompressor.write_to_file(
+            path.to_string_lossy().into_owned(),
+            Some(Uint8Array::new(data.to_vec())),
+        )
+    };
+
+    // Fails in the gzip header, before any output
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+    assert!(write_to_file(&mut decompressor, b"not gzip at all").is_err());
+
+    // Already finished
+    let mut decompressor = HttpDecompressor::new_for_http_response("identity".to_string()).unwrap();
+    assert!(decompressor.finish().unwrap().ok);
+    assert!(write_to_file(&mut decompressor, b"too late").is_err());
+
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+    decompressor.dispose();
+    assert!(write_to_file(&mut decompressor, &gzipped(b"too late")).is_err());
+
+    let contents = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(contents, b"keep me");
+}
+
+#[test]
+fn deflate_compressor_reports_the_window_bits_it_uses() {
+    let window_bits = |bits: i32| {
+        DeflateCompressor::with_options(CompressorOptions {
+            window_bits: Some(bits),
+            ..CompressorOptions::default()
+        })
+        .unwrap()
+        .window_bits()
+    };
+
+    // deflate never uses a 256-byte window, zlib-wrapped streams asking for one get 512 bytes instead
+    assert_eq!(window_bits(8), 9);
+    assert_eq!(window_bits(9), 9);
+    assert_eq!(window_bits(15), 15);
+    assert_eq!(window_bits(-9), -9);
+    assert_eq!(window_bits(31), 31);
+}
+
+#[test]
+fn deflate_copy_compresses_like_the_original() {
+    let data = sample();
+    let (head, tail) = data.split_at(data.len() / 2);
+
+    let mut original = DeflateCompressor::new(None, None).unwrap();
+    let checkpoint = flush_message(&mut original, head);
+    let mut copy = deflate_copy(&original).unwrap();
+
+    let from_original = flush_message(&mut original, tail);
+    let from_copy = flush_message(&mut copy, tail);
+    assert_eq!(from_original, from_copy);
+
+    // Both carry on from the checkpoint, so either continuation inflates back to the data
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+    let mut output = into_bytes(decompressor.push(Uint8Array::new(checkpoint)).unwrap().data);
+    output.extend(into_bytes(
+        decompressor.push(Uint8Array::new(from_copy)).unwrap().data,
+    ));
+    assert_eq!(output, data);
+}
+
+#[test]
+fn deflate_copy_outlives_the_original() {
+    let data = sample();
+
+    let mut original = DeflateCompressor::with_options(CompressorOptions {
+        window_bits: Some(31),
+        ..CompressorOptions::default()
+    })
+    .unwrap();
+    original
+        .set_header(GzipHeader {
+            name: Some("copied.txt".to_string()),
+            ..GzipHeader::default()
+        })
+        .unwrap();
+
+    // The header hasn't been written yet, so the copy has to hold on to its own
+    let mut copy = deflate_copy(&original).unwrap();
+    original.dispose();
+    drop(original);
+
+    let pushed = copy.push(Uint8Array::new(data.clone())).unwrap();
+    assert!(pushed.ok, "{:?}", pushed.error);
+    let finished = copy.finish(FlushMode::Finish).unwrap();
+    assert!(finished.ok, "{:?}", finished.error);
+
+    let mut compressed = into_bytes(pushed.data);
+    compressed.extend(into_bytes(finished.data));
+
+    // FNAME is set and the name follows the fixed 10-byte header
+    assert_ne!(compressed[3] & 0x08, 0);
+    assert_eq!(&compressed[10..21], b"copied.txt\0");
+
+    let frames = decompress_all_frames(compressed.into(), CompressionFormat::Gzip).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].to_vec(), data);
+}
+
+#[test]
+fn zlib_decompressor_inflate_sync_resumes_after_corruption() {
+    let mut compressor = DeflateCompressor::new(None, None).unwrap();
+    let mut decompressor = ZlibDecompressor::new(None, None).unwrap();
+
+    let first = flush_message(&mut compressor, b"first message");
+    let result = decompressor.push(Uint8Array::new(first)).unwrap();
+    assert_eq!(into_bytes(result.data), b"first message");
+
+    // A full flush resets the compression state, so the next message doesn't reference anything lost to corruption
+    let pushed = compressor
+        .push(Uint8Array::new(b"lost message".to_vec()))
+        .unwrap();
+    let flushed = compressor.finish(FlushMode::Full).unwrap();
+    let mut lost = into_bytes(pushed.data);
+    lost.extend(into_bytes(flushed.data));
+    let second = flush_message(&mut compressor, b"second message");
+
+    // Invalid block types everywhere but the flush point that ends the lost message
+    let mut corrupted = vec![0xff; lost.len() - Z_SYNC_FLUSH_SUFFIX.len()];
+    corrupted.extend_from_slice(Z_SYNC_FLUSH_SUFFIX);
+
+    let result = decompressor
+        .push(Uint8Array::new(corrupted.clone()))
+        .unwrap();
+    assert!(!result.ok);
+
+    let synced = decompressor.inflate_sync(Buffer::from(corrupted)).unwrap();
+    assert!(synced.ok);
+
+    let result = decompressor.push(Uint8Array::new(second)).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), b"second message");
+}
+
+#[test]
+fn deflate_compressor_write_stored_block_round_trips() {
+    let mut compressor = DeflateCompressor::new(None, None).unwrap();
+    let stored = b"already compressed, keep as is".to_vec();
+
+    let mut compressed = into_bytes(
+        compressor
+            .push(Uint8Array::new(b"deflated before, ".to_vec()))
+            .unwrap()
+            .data,
+    );
+    let written = compressor
+        .write_stored_block(Buffer::from(stored.clone()))
+        .unwrap();
+    compressed.extend_from_slice(&written);
+    compressed.extend(into_bytes(
+        compressor
+            .push(Uint8Array::new(b", deflated after".to_vec()))
+            .unwrap()
+            .data,
+    ));
+    compressed.extend(into_bytes(
+        compressor.finish(FlushMode::Finish).unwrap().data,
+    ));
+
+    // Stored blocks hold the data as is
+    assert!(written.windows(stored.len()).any(|window| window == stored));
+
+    let frames = decompress_all_frames(Buffer::from(compressed), CompressionFormat::Zlib).unwrap();
+    assert_eq!(
+        frames[0].to_vec(),
+        b"deflated before, already compressed, keep as is, deflated after"
+    );
+}
+
+#[test]
+fn deflate_compressor_set_header_round_trips() {
+    let options = CompressorOptions {
+        window_bits: Some(31),
+        ..CompressorOptions::default()
+    };
+    let mut compressor = DeflateCompressor::new(Some(Either::B(options)), None).unwrap();
+
+    compressor
+        .set_header(GzipHeader {
+            mtime: Some(1_700_000_000),
+            name: Some("data.txt".to_string()),
+            comment: Some("made in a test".to_string()),
+            header_crc: Some(true),
+            ..GzipHeader::default()
+        })
+        .unwrap();
+
+    let data = sample();
+    let mut compressed = into_bytes(compressor.push(Uint8Array::new(data.clone())).unwrap().data);
+    compressed.extend(into_bytes(
+        compressor.finish(FlushMode::Finish).unwrap().data,
+    ));
+
+    assert_eq!(&compressed[4..8], &1_700_000_000u32.to_le_bytes());
+    assert!(compressed
+        .windows(b"made in a test\0".len())
+        .any(|window| window == b"made in a test\0"));
+
+    let mut decompressor = HttpDecompressor::new_for_http_response("gzip".to_string()).unwrap();
+    let result = decompressor.push(Uint8Array::new(compressed)).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), data);
+    assert!(decompressor.finish().unwrap().ok);
+}
+
+#[test]
+fn deflate_compressor_bounds_its_own_stream() {
+    let compressor = |window_bits| {
+        DeflateCompressor::with_options(CompressorOptions {
+            window_bits: Some(window_bits),
+            ..CompressorOptions::default()
+        })
+        .unwrap()
+    };
+
+    let raw = compressor(-15).compress_bound(1000).unwrap();
+    let zlib = compressor(15).compress_bound(1000).unwrap();
+    assert_eq!(zlib, raw + 6);
+
+    let mut gzip = compressor(31);
+    assert_eq!(gzip.compress_bound(1000).unwrap(), raw + 18);
+
+    gzip.set_header(GzipHeader {
+        name: Some("data.txt".to_string()),
+        header_crc: Some(true),
+        ..GzipHeader::default()
+    })
+    .unwrap();
+    assert_eq!(gzip.compress_bound(1000).unwrap(), raw + 18 + 9 + 2);
+
+    // Bytes that deflate can't do anything with still fit
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 7919 % 251) as u8).collect();
+    let mut zlib_compressor = compressor(15);
+    let bound = zlib_compressor.compress_bound(data.len() as u32).unwrap();
+    let mut compressed = into_bytes(zlib_compressor.push(Uint8Array::new(data)).unwrap().data);
+    compressed.extend(into_bytes(
+        zlib_compressor.finish(FlushMode::Finish).unwrap().data,
+    ));
+    assert!(compressed.len() <= bound as usize);
+
+    zlib_compressor.dispose();
+    assert!(zlib_compressor.compress_bound(1000).is_err());
+}
+
+#[test]
+fn text_decompressor_decodes_messages() {
+    let mut compressor = DeflateCompressor::new(None, None).unwrap();
+    let mut decompressor = TextDecompressor::new(None, None).unwrap();
+
+    let result = decompressor
+        .push(Uint8Array::new(flush_message(
+            &mut compressor,
+            "héllo wörld".as_bytes(),
+        )))
+        .unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(result.text.as_deref(), Some("héllo wörld"));
+    assert!(!result.finished);
+
+    // Invalid UTF-8 only drops that message
+    let result = decompressor
+        .push(Uint8Array::new(flush_message(
+            &mut compressor,
+            &[0xc3, 0x28],
+        )))
+        .unwrap();
+    assert!(!result.ok);
+    assert!(result.error.unwrap().starts_with("Invalid UTF-8"));
+
+    let result = decompressor
+        .push(Uint8Array::new(flush_message(
+            &mut compressor,
+            b"still here",
+        )))
+        .unwrap();
+    assert_eq!(result.text.as_deref(), Some("still here"));
+}
+
+/// Pushes `compressed` one `chunk_len` sized chunk at a time, then finishes, returning all of the output.
+#[cfg(any(feature = "brotli", feature = "zstd", feature = "lz4"))]
+fn push_in_chunks<D>(
+    decompressor: &mut D,
+    compressed: &[u8],
+    chunk_len: usize,
+    push: fn(&mut D, Uint8Array) -> napi::Result<PushResult>,
+    finish: fn(&mut D) -> napi::Result<FinishResult>,
+) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    for chunk in compressed.chunks(chunk_len) {
+        let result = push(decompressor, Uint8Array::new(chunk.to_vec())).unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        output.extend(into_bytes(result.data));
+    }
+
+    let result = finish(decompressor).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    output.extend(into_bytes(result.data));
+
+    output
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn brotli_round_trips() {
+    use vladfrangu_dev_compression::brotli::{BrotliCompressor, BrotliDecompressor};
+
+    let data = sample();
+
+    let mut compressor = BrotliCompressor::new(16 * 1024, 5, 22).unwrap();
+    let mut compressed = into_bytes(compressor.push(Uint8Array::new(data.clone())).unwrap().data);
+    compressed.extend(into_bytes(compressor.finish().unwrap().data));
+
+    let mut decompressor = BrotliDecompressor::new(16 * 1024).unwrap();
+    let output = push_in_chunks(
+        &mut decompressor,
+        &compressed,
+        7,
+        BrotliDecompressor::push,
+        BrotliDecompressor::finish,
+    );
+    assert_eq!(output, data);
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn brotli_decompressor_restarts() {
+    use vladfrangu_dev_compression::brotli::{BrotliCompressor, BrotliDecompressor};
+
+    let data = sample();
+
+    let mut compressor = BrotliCompressor::new(16 * 1024, 5, 22).unwrap();
+    let mut compressed = into_bytes(compressor.push(Uint8Array::new(data.clone())).unwrap().data);
+    compressed.extend(into_bytes(compressor.finish().unwrap().data));
+
+    // Abandon the first stream halfway through
+    let mut decompressor = BrotliDecompressor::new(16 * 1024).unwrap();
+    let half = compressed[..compressed.len() / 2].to_vec();
+    assert!(decompressor.push(Uint8Array::new(half)).unwrap().ok);
+
+    decompressor.restart().unwrap();
+
+    // Then decompress the whole thing twice, restarting in between
+    for _ in 0..2 {
+        let output = push_in_chunks(
+            &mut decompressor,
+            &compressed,
+            7,
+            BrotliDecompressor::push,
+            BrotliDecompressor::finish,
+        );
+        assert_eq!(output, data);
+        assert_eq!(decompressor.bytes_decompressed(), data.len() as f64);
+
+        decompressor.restart().unwrap();
+    }
+
+    decompressor.dispose();
+    assert!(decompressor.restart().is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_round_trips() {
+    use vladfrangu_dev_compression::zstd::{ZstdCompressor, ZstdDecompressor};
+
+    let data = sample();
+
+    let mut compressor = ZstdCompressor::new(16 * 1024, 3, None).unwrap();
+    let mut compressed = into_bytes(compressor.push(Uint8Array::new(data.clone())).unwrap().data);
+    compressed.extend(into_bytes(compressor.finish().unwrap().data));
+
+    let mut decompressor = ZstdDecompressor::new(16 * 1024, None).unwrap();
+    let output = push_in_chunks(
+        &mut decompressor,
+        &compressed,
+        7,
+        ZstdDecompressor::push,
+        ZstdDecompressor::finish,
+    );
+    assert_eq!(output, data);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_compressor_sets_the_overlap_log() {
+    use vladfrangu_dev_compression::zstd::{ZstdCompressor, ZstdDecompressor};
+
+    let data = sample();
+
+    let mut compressor = ZstdCompressor::new(16 * 1024, 3, None).unwrap();
+    assert!(compressor.set_overlap_log(10).is_err());
+    compressor.set_overlap_log(9).unwrap();
+
+    let mut compressed = into_bytes(compressor.push(Uint8Array::new(data.clone())).unwrap().data);
+    compressed.extend(into_bytes(compressor.finish().unwrap().data));
+
+    let mut decompressor = ZstdDecompressor::new(16 * 1024, None).unwrap();
+    let output = push_in_chunks(
+        &mut decompressor,
+        &compressed,
+        7,
+        ZstdDecompressor::push,
+        ZstdDecompressor::finish,
+    );
+    assert_eq!(output, data);
+
+    compressor.dispose();
+    assert!(compressor.set_overlap_log(0).is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_compressor_writes_empty_frames() {
+    use vladfrangu_dev_compression::zstd::{ZstdCompressor, ZstdDecompressor};
+
+    let mut compressor = ZstdCompressor::new(16 * 1024, 3, None).unwrap();
+    let frame = compressor.write_empty_block().unwrap().to_vec();
+
+    let mut decompressor = ZstdDecompressor::new(16 * 1024, None).unwrap();
+    let output = push_in_chunks(
+        &mut decompressor,
+        &frame,
+        7,
+        ZstdDecompressor::push,
+        ZstdDecompressor::finish,
+    );
+    assert!(output.is_empty());
+
+    compressor.dispose();
+    assert!(compressor.write_empty_block().is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_decompressor_does_not_finish_a_broken_stream() {
+    use vladfrangu_dev_compression::zstd::ZstdDecompressor;
+
+    // Fails on the magic number, before any frame was started
+    let mut decompressor = ZstdDecompressor::new(16 * 1024, None).unwrap();
+    let result = decompressor
+        .push(Uint8Array::new(b"not a zstd frame".to_vec()))
+        .unwrap();
+    assert!(!result.ok);
+
+    let finished = decompressor.finish().unwrap();
+    assert!(!finished.ok);
+    assert_eq!(finished.error, result.error);
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn lz4_round_trips() {
+    use std::io::Write;
+    use vladfrangu_dev_compression::lz4::{Lz4Decompressor, Lz4Format};
+
+    let data = sample();
+
+    // Two frames back to back
+    let mut compressed = Vec::new();
+    for _ in 0..2 {
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        encoder.write_all(&data).unwrap();
+        compressed.extend(encoder.finish().unwrap());
+    }
+
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Frame, None);
+    let output = push_in_chunks(
+        &mut decompressor,
+        &compressed,
+        7,
+        Lz4Decompressor::push,
+        Lz4Decompressor::finish,
+    );
+    assert_eq!(output, [data.clone(), data.clone()].concat());
+
+    let compressed = lz4_flex::compress_prepend_size(&data);
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Block, None);
+    let output = push_in_chunks(
+        &mut decompressor,
+        &compressed,
+        7,
+        Lz4Decompressor::push,
+        Lz4Decompressor::finish,
+    );
+    assert_eq!(output, data);
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn lz4_decompressor_counts_block_checksum_errors() {
+    use lz4_flex::frame::{FrameEncoder, FrameInfo};
+    use std::io::Write;
+    use vladfrangu_dev_compression::lz4::{Lz4Decompressor, Lz4Format};
+
+    let data = sample();
+
+    let mut encoder =
+        FrameEncoder::with_frame_info(FrameInfo::new().block_checksums(true), Vec::new());
+    encoder.write_all(&data).unwrap();
+    let frame = encoder.finish().unwrap();
+
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Frame, None);
+    let result = decompressor.push(Uint8Array::new(frame.clone())).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(decompressor.block_checksum_errors(), 0);
+
+    // Break the checksum of the first block, which comes right after the 7-byte header and the block itself
+    let block_size = u32::from_le_bytes(frame[7..11].try_into().unwrap()) & 0x7fff_ffff;
+    let mut broken = frame.clone();
+    broken[11 + block_size as usize] ^= 0xff;
+
+    for _ in 0..2 {
+        let result = decompressor.push(Uint8Array::new(broken.clone())).unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        assert_eq!(into_bytes(result.data), data);
+    }
+    assert_eq!(decompressor.block_checksum_errors(), 2);
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn lz4_decompressor_rejects_oversized_blocks() {
+    use vladfrangu_dev_compression::lz4::{Lz4Decompressor, Lz4Format};
+
+    // A frame with 64 KiB blocks (BD 0x40) whose first block claims to be 1 MiB
+    let mut frame = vec![0x04, 0x22, 0x4d, 0x18, 0x60, 0x40, 0x82];
+    frame.extend_from_slice(&(1024u32 * 1024).to_le_bytes());
+
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Frame, None);
+    let result = decompressor.push(Uint8Array::new(frame)).unwrap();
+    assert!(!result.ok);
+    assert!(result
+        .error
+        .unwrap()
+        .contains("larger than the frame's maximum"));
+
+    // A block claiming more than the limit is rejected before anything is allocated
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Block, Some(1024));
+    let result = decompressor
+        .push(Uint8Array::new(u32::MAX.to_le_bytes().to_vec()))
+        .unwrap();
+    assert!(!result.ok);
+    assert!(result
+        .error
+        .unwrap()
+        .contains("larger than the maximum of 1024"));
+
+    // As is input that keeps on coming past what the declared size could compress to
+    let mut decompressor = Lz4Decompressor::new(Lz4Format::Block, None);
+    let result = decompressor
+        .push(Uint8Array::new(16u32.to_le_bytes().to_vec()))
+        .unwrap();
+    assert!(result.ok);
+    let result = decompressor.push(Uint8Array::new(vec![0; 64])).unwrap();
+    assert!(!result.ok);
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn snappy_round_trips() {
+    use vladfrangu_dev_compression::snappy::{SnappyCompressor, SnappyDecompressor};
+
+    let mut compressor = SnappyCompressor::new();
+    let mut decompressor = SnappyDecompressor::new(None);
+
+    // Every block stands on its own
+    for block in [sample(), Vec::new(), b"short".to_vec()] {
+        let compressed = compressor.push(Uint8Array::new(block.clone())).unwrap();
+        assert!(compressed.ok, "{:?}", compressed.error);
+
+        let result = decompressor
+            .push(Uint8Array::new(into_bytes(compressed.data)))
+            .unwrap();
+        assert!(result.ok, "{:?}", result.error);
+        assert_eq!(into_bytes(result.data), block);
+    }
+
+    assert!(decompressor.finish().unwrap().ok);
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn snappy_decompressor_rejects_oversized_blocks() {
+    use vladfrangu_dev_compression::snappy::{SnappyCompressor, SnappyDecompressor};
+
+    // Just a header claiming close to 4 GiB
+    let mut decompressor = SnappyDecompressor::new(None);
+    let result = decompressor
+        .push(Uint8Array::new(vec![0xff, 0xff, 0xff, 0xff, 0x0f]))
+        .unwrap();
+    assert!(!result.ok);
+    assert!(result
+        .error
+        .unwrap()
+        .contains("larger than the maximum of 67108864"));
+
+    let block = into_bytes(
+        SnappyCompressor::new()
+            .push(Uint8Array::new(sample()))
+            .unwrap()
+            .data,
+    );
+
+    let mut decompressor = SnappyDecompressor::new(Some(1024));
+    let result = decompressor.push(Uint8Array::new(block.to_vec())).unwrap();
+    assert!(!result.ok);
+
+    let mut decompressor = SnappyDecompressor::new(Some(sample().len() as u32));
+    let result = decompressor.push(Uint8Array::new(block.to_vec())).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), sample());
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn snappy_compressor_limits_blocks_to_the_chunk_size() {
+    use vladfrangu_dev_compression::snappy::{SnappyCompressor, SnappyDecompressor};
+
+    let mut compressor = SnappyCompressor::new();
+    assert!(compressor.set_chunk_size(0).is_err());
+    assert!(compressor.push(Uint8Array::new(sample())).unwrap().ok);
+
+    compressor.set_chunk_size(1024).unwrap();
+    assert!(!compressor.push(Uint8Array::new(sample())).unwrap().ok);
+
+    let block = into_bytes(
+        compressor
+            .push(Uint8Array::new(vec![b'x'; 1024]))
+            .unwrap()
+            .data,
+    );
+    let result = SnappyDecompressor::new(Some(1024))
+        .push(Uint8Array::new(block.to_vec()))
+        .unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), vec![b'x'; 1024]);
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn snappy_decompressor_resets_after_a_failure() {
+    use vladfrangu_dev_compression::snappy::{SnappyCompressor, SnappyDecompressor};
+
+    let block = into_bytes(
+        SnappyCompressor::new()
+            .push(Uint8Array::new(b"after the reset".to_vec()))
+            .unwrap()
+            .data,
+    );
+
+    let mut decompressor = SnappyDecompressor::new(None);
+    assert!(!decompressor.push(Uint8Array::new(vec![0xff])).unwrap().ok);
+
+    // Finished until reset
+    let result = decompressor.push(Uint8Array::new(block.to_vec())).unwrap();
+    assert_eq!(into_bytes(result.data), b"");
+
+    decompressor.reset().unwrap();
+    let result = decompressor.push(Uint8Array::new(block.to_vec())).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+    assert_eq!(into_bytes(result.data), b"after the reset");
+
+    decompressor.dispose();
+    assert!(decompressor.reset().is_err());
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn snappy_compressor_stays_within_its_budget() {
+    use vladfrangu_dev_compression::snappy::SnappyCompressor;
+
+    assert!(SnappyCompressor::new_with_budget(0).is_err());
+
+    // 1000 bytes can compress to at most 1000 + 1000 / 6 + 32 = 1198 bytes
+    let mut compressor = SnappyCompressor::new_with_budget(1300).unwrap();
+    let first = compressor.push(Uint8Array::new(vec![0; 1000])).unwrap();
+    assert!(first.ok, "{:?}", first.error);
+    let used = into_bytes(first.data).len();
+    assert!(used < 100);
+
+    // Would fit if it compressed as well, but the worst case doesn't
+    let result = compressor
+        .push(Uint8Array::new(vec![0; 1300 - used]))
+        .unwrap();
+    assert!(!result.ok);
+    assert_eq!(result.error.as_deref(), Some("budget exceeded"));
+
+    let result = compressor.push(Uint8Array::new(vec![0; 1000])).unwrap();
+    assert!(result.ok, "{:?}", result.error);
+}
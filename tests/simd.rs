@@ -0,0 +1,83 @@
+//! Checks the checksums zlib-rs computes with its SIMD kernels against plain scalar implementations, so the `simd`
+//! feature can't change results. Throughput is measured by the `checksum` benchmark instead.
+//!
+//! zlib-rs picks its kernels at runtime, so this covers whichever ones the machine supports. The AVX-512 ones are only
+//! compiled in with the matching target features: `RUSTFLAGS="-C target-cpu=native" cargo test --features simd`.
+#![cfg(feature = "simd")]
+
+/// Bit-at-a-time CRC-32 (the reflected 0xEDB88320 polynomial zlib uses).
+fn scalar_crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Adler-32 straight from its definition in RFC 1950.
+fn scalar_adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+fn pseudo_random(len: usize) -> Vec<u8> {
+    (0..len as u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+        .collect()
+}
+
+/// Short inputs at every alignment within a 64-byte vector, then lengths around the block sizes the kernels work in,
+/// so both their main loops and their leftover handling get exercised.
+fn slices(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let short = (0..=256).flat_map(|len| (0..64).map(move |offset| (offset, len)));
+    let long = [511, 512, 513, 1023, 4096, 5552, 65_537, 1 << 20]
+        .into_iter()
+        .flat_map(|len| [0, 1, 31, 63].map(|offset| (offset, len)));
+
+    short
+        .chain(long)
+        .map(move |(offset, len)| &data[offset..offset + len])
+}
+
+#[test]
+fn crc32_matches_the_scalar_implementation() {
+    let data = pseudo_random((1 << 20) + 64);
+
+    for slice in slices(&data) {
+        assert_eq!(
+            zlib_rs::crc32(0, slice),
+            scalar_crc32(slice),
+            "length {}",
+            slice.len()
+        );
+    }
+}
+
+#[test]
+fn adler32_matches_the_scalar_implementation() {
+    let data = pseudo_random((1 << 20) + 64);
+
+    for slice in slices(&data) {
+        assert_eq!(
+            zlib_rs::adler32(1, slice),
+            scalar_adler32(slice),
+            "length {}",
+            slice.len()
+        );
+    }
+}
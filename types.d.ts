@@ -0,0 +1,73 @@
+/* Hand-written, unlike the index.d.ts NAPI-RS generates next to it, which gets overwritten on every build. */
+
+/*
+ * Classes behind a Cargo feature are always declared, but only exist in binaries built with that feature enabled, which
+ * the `build` script doesn't do by default. Anywhere else their exports are `undefined`:
+ *
+ *   BrotliCompressor, BrotliDecompressor: brotli
+ *   JsonDecompressor: json
+ *   Lz4Decompressor: lz4
+ *   SnappyCompressor, SnappyDecompressor: snappy
+ *   ZstdCompressor, ZstdDecompressor: zstd
+ */
+
+export * from './index'
+
+/*
+ * napi-rs can't name methods after symbols, so `[Symbol.dispose]` is patched onto every class with a `dispose` method
+ * when the module loads (see `init` in src/lib.rs), and declared here by merging into the generated classes.
+ */
+declare module './index' {
+  interface BrotliCompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface BrotliDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface DeflateCompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface HttpDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface ZlibDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface ZstdDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface ZstdCompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface Lz4Decompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface SnappyDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface SnappyCompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface MultiFormatDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface TextDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+  interface JsonDecompressor {
+    /** Same as `dispose`, so instances can be declared with `using`. */
+    [Symbol.dispose](): void
+  }
+}